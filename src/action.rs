@@ -0,0 +1,86 @@
+//! Everything a player can do on their turn, as one value.
+//! The keyboard and mouse, the AI, a network peer and a replay all build an `Action` and hand it to
+//! `Game::apply`, so there is a single entry point into the rules and actions can be saved and sent.
+
+/* ------ Import Used Libraries ------ */
+use crate::twist::ActionType;
+// Saving and loading
+use serde::{Deserialize, Serialize};
+
+/*------ Enums and Structs ------ */
+/// Which way a patrolling ship moves.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)] // Copy - Enables bitwise copying of the type. Hash - Allows use as a map key. Serialize/Deserialize - Allows saving and sending
+pub enum Direction {
+    Up,    // Towards row 0
+    Down,  // Towards the last row
+    Left,  // Towards column 0
+    Right, // Towards the last column
+}
+
+/// A single thing a player can do.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)] // Copy - Enables bitwise copying of the type. Hash - Allows use as a map key. Serialize/Deserialize - Allows saving and sending
+pub enum Action {
+    Missile { x: usize, y: usize },           // Fire at the opponent's (x, y)
+    Torpedo { col: usize },                   // Fire up the opponent's column from the bottom row (Twist only)
+    RadarScan { x: usize, y: usize },         // Reveal the opponent's (x, y) and its neighbours (Twist only)
+    Reinforce { x: usize, y: usize },         // Give one of your own ship cells an extra life (Twist only)
+    Patrol { ship: usize, dir: Direction },   // Move your ship with that index one cell (Twist only)
+    EndTurn,                                  // Finish the turn, or pass if nothing else can be done
+    Resign,                                   // Give the game to the opponent, allowed on any of your turns
+}
+
+/* ------ Struct Implementations ------ */
+impl Direction {
+    /// Every direction, for trying each in turn
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    /// Change in (row, column) for one step
+    pub fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+        }
+    }
+
+    /// The direction with this (row, column) step, if there is one
+    pub fn from_delta(dir_x: isize, dir_y: isize) -> Option<Direction> {
+        Direction::ALL.into_iter().find(|dir| dir.delta() == (dir_x, dir_y))
+    }
+}
+
+impl Action {
+    /// The card this action uses in Twist mode, ending the turn or resigning doesn't use one.
+    pub fn card(&self) -> Option<ActionType> {
+        match self {
+            Action::Missile { .. } => Some(ActionType::Missile),
+            Action::Torpedo { .. } => Some(ActionType::Torpedo),
+            Action::RadarScan { .. } => Some(ActionType::RadarScan),
+            Action::Reinforce { .. } => Some(ActionType::Reinforce),
+            Action::Patrol { .. } => Some(ActionType::Patrol),
+            Action::EndTurn | Action::Resign => None,
+        }
+    }
+}
+
+/* ------ Functions ------ */
+/// Name of the cell at (x, y) as players write it, the row's letter then the column from 1, e.g. (2, 6) is `C7`.
+pub fn cell_name(x: usize, y: usize) -> String {
+    format!("{}{}", (b'A' + x as u8) as char, y + 1)
+}
+
+/// Reads a cell name such as `C7` or `c7` back into (x, y), None if it isn't one.
+/// The cell isn't checked against any board.
+pub fn parse_cell(name: &str) -> Option<(usize, usize)> {
+    let mut chars = name.chars();
+    let row = chars.next()?.to_ascii_uppercase();
+    if !row.is_ascii_uppercase() {
+        return None;
+    }
+    let column: usize = chars.as_str().parse().ok()?;
+    if column == 0 {
+        return None;
+    }
+    Some((row as usize - 'A' as usize, column - 1))
+}
@@ -0,0 +1,894 @@
+//! Computer opponents.
+//! An `Opponent` looks at the game the same way a player would (their own boards and guess board,
+//! never the other fleet) and picks one of `Game::legal_actions`, which the caller passes to `Game::apply`.
+//! Each opponent has its own seeded RNG so a seeded game against the computer plays out the same way again.
+
+/* ------ Import Used Libraries ------ */
+use crate::action::Action;
+use crate::base::*;
+use crate::game::Game;
+use crate::placement::Strategy;
+use crate::rules::Mode;
+use crate::search::{Budget, MonteCarlo};
+use crate::twist::ActionType;
+// Random library
+use ::rand::prelude::*;
+use ::rand::rngs::StdRng;
+// Saving and loading
+use serde::{Deserialize, Serialize};
+
+/*------ Constants ------ */
+const TARGET_WEIGHT: f64 = 20.0; // How much more a placement counts for each unsunk hit it explains
+const PEEK_CHANCE: f64 = 0.2;    // How often Expert looks at the real board instead of hunting
+const RADAR_WEIGHT: f64 = 0.5;   // A ship cell found by radar is worth this much of a hit, it still has to be fired at
+const REINFORCE_WEIGHT: f64 = 0.8; // A hit a reinforcement is expected to soak up is worth this much of a hit of our own
+const PATROL_WEIGHT: f64 = 0.8;  // Same again for each hit a patrol is expected to dodge
+const HABIT_WEIGHT: f64 = 2.0;   // How much more a cell is worth to probability density where the opponent's ships always end up
+
+/*------ Traits ------ */
+/// Something that can take a player's turn.
+/// `Send` so the window can leave it thinking on another thread while it keeps drawing.
+pub trait Opponent: Send {
+    /// Picks the next action for `id`, always one of `game.legal_actions(id)` or `Action::Resign`.
+    /// Called again after each action until it returns `Action::EndTurn`.
+    fn choose_action(&mut self, game: &Game, id: PlayerId) -> Action;
+
+    /// Called once before the game starts with the seat `id` will play, for players that need to
+    /// subscribe to the game's events. Does nothing unless overridden.
+    fn join(&mut self, _game: &mut Game, _id: PlayerId) {}
+
+    /// Called before the game starts with how often the opponent's ships have ended up on each cell
+    /// in past games, from 0 to 1 (see `Habits::ship_heat`). Ignored unless overridden.
+    fn study(&mut self, _ship_heat: &[Vec<f64>]) {}
+}
+
+/*------ Enums and Structs ------ */
+/// How strong the computer opponent is.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)] // Copy - Enables bitwise copying of the type. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}. Serialize/Deserialize - Allows saving and loading
+pub enum Difficulty {
+    Easy,   // Random shots
+    Normal, // Hunt and target
+    Hard,   // Probability density
+    Expert, // Probability density that sometimes cheats
+    Master, // Searches ahead through games that fit what it has seen
+}
+
+/// Fires at a random cell it hasn't tried yet.
+pub struct RandomShots {
+    rng: StdRng, // Picks the cell
+}
+
+/// Classic hunt and target play.
+/// While hunting it fires at random cells on a checkerboard spaced by the smallest ship left afloat,
+/// after a hit it works the neighbours and then follows the line of hits until the ship is sunk.
+pub struct HuntTarget {
+    rng: StdRng, // Picks between equally good cells
+}
+
+/// Fires at whichever cell the most possible layouts of the remaining ships cover.
+/// Works only from its own guess board and which ships it has sunk, see `probability_density`.
+/// Told the opponent's habits with `study`, it favours the cells their ships usually end up on.
+pub struct ProbabilityDensity {
+    rng: StdRng,                      // Picks between equally likely cells
+    ship_heat: Option<Vec<Vec<f64>>>, // Where the opponent's ships have ended up before, if known
+}
+
+/// Probability density with a limited cheat: while it has no damaged ship to finish off, it sometimes
+/// looks at the opponent's real board and fires at a ship cell. Makes a hard game for experienced players.
+pub struct Peeking {
+    density: ProbabilityDensity, // Plays every turn it doesn't cheat
+    rng: StdRng,                 // Decides when to cheat and where
+}
+
+/// Twist player that weighs up every card in its hand instead of only firing.
+/// Each card is scored in expected hits: a missile by the best cell on offer, a torpedo by the chance it finds a ship
+/// before stopping, a radar scan by the ship cells it should uncover, and reinforcing or patrolling by how likely
+/// the opponent is to hit the cells involved. Which cell a missile goes at is left to the wrapped opponent,
+/// so in Classic, where there are only missiles, it plays exactly like that opponent.
+pub struct CardPlayer {
+    targeting: Box<dyn Opponent>, // Picks the cell when a missile is the best card
+    rng: StdRng,                  // Picks between equally good cards
+}
+
+/// What the shot advisor suggests to a player, worked out only from what they can see.
+/// Used by the ui to coach newcomers, it never picks anything for them.
+pub struct Advice {
+    pub chances: Vec<Vec<f64>>,         // Rough chance each cell of the opponent's board holds a ship, see `hit_chances`
+    pub target: Option<(usize, usize)>, // Cell a missile is most likely to hit, None once every cell has been fired at
+    pub torpedo: Option<usize>,         // Column a torpedo is most likely to find a ship in, only in Twist with a torpedo in hand
+}
+
+/* ------ Struct Implementations ------ */
+impl Difficulty {
+    /// Every level, easiest first
+    pub const ALL: [Difficulty; 5] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Expert, Difficulty::Master];
+
+    /// Reads a level name as typed on the command line, e.g. "hard".
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL.into_iter().find(|level| level.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Name shown to players
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Expert => "Expert",
+            Difficulty::Master => "Master",
+        }
+    }
+
+    /// Creates the opponent for this level.
+    /// Easy plays Twist cards at random, Normal to Expert weigh them up with `CardPlayer` and Master searches.
+    pub fn opponent(&self, seed: u64) -> Box<dyn Opponent> {
+        self.opponent_with_budget(seed, Budget::default())
+    }
+
+    /// Creates the opponent for this level, giving Master `budget` to think about each move.
+    pub fn opponent_with_budget(&self, seed: u64, budget: Budget) -> Box<dyn Opponent> {
+        match self {
+            Difficulty::Easy => Box::new(RandomShots::new(seed)),
+            Difficulty::Normal => Box::new(CardPlayer::new(Box::new(HuntTarget::new(seed)), seed)),
+            Difficulty::Hard => Box::new(CardPlayer::new(Box::new(ProbabilityDensity::new(seed)), seed)),
+            Difficulty::Expert => Box::new(CardPlayer::new(Box::new(Peeking::new(seed)), seed)),
+            Difficulty::Master => Box::new(MonteCarlo::new(budget, seed)),
+        }
+    }
+
+    /// Returns true if this level learns from the profile of the person it plays, see `profile.rs`.
+    /// Easy and Normal don't, the way they play has no room for a hunch.
+    pub fn learns(&self) -> bool {
+        matches!(self, Difficulty::Hard | Difficulty::Expert | Difficulty::Master)
+    }
+
+    /// Returns true if this level also lays its fleet out around where the person it plays fires first.
+    /// Hard only learns where their ships tend to be, so its fleet keeps to the layouts it picks from.
+    pub fn adapts_layout(&self) -> bool {
+        matches!(self, Difficulty::Expert | Difficulty::Master)
+    }
+
+    /// Fleet layouts this level picks from, so there isn't one pattern to learn.
+    pub fn layouts(&self) -> &'static [Strategy] {
+        match self {
+            Difficulty::Easy => &[Strategy::Random],
+            Difficulty::Normal => &[Strategy::Random, Strategy::EdgeHugging, Strategy::Clustered],
+            Difficulty::Hard => &[Strategy::EdgeHugging, Strategy::SpreadOut, Strategy::AntiDensity],
+            Difficulty::Expert | Difficulty::Master => &[Strategy::SpreadOut, Strategy::AntiDensity],
+        }
+    }
+
+    /// Picks the layout for the computer's fleet in a game, the same seed always picks the same one.
+    pub fn layout(&self, seed: u64) -> Strategy {
+        let mut rng = StdRng::seed_from_u64(seed);
+        *self.layouts().choose(&mut rng).expect("every level has a layout")
+    }
+}
+
+impl RandomShots {
+    /// Creates the opponent, the same seed always makes the same choices in the same game.
+    pub fn new(seed: u64) -> Self {
+        RandomShots {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl HuntTarget {
+    /// Creates the opponent, the same seed always makes the same choices in the same game.
+    pub fn new(seed: u64) -> Self {
+        HuntTarget {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Cells to try next while a damaged ship is still afloat, empty if there isn't one.
+    pub(crate) fn target_cells(&self, player: &BasePlayer) -> Vec<(usize, usize)> {
+        let board = &player.guess_board;
+        let open = open_hits(player);
+
+        // Ends of a line of two or more hits, the ship most likely carries on along it
+        let mut line = Vec::new();
+        for &(x, y) in &open {
+            for (dx, dy) in [(0, 1), (1, 0)] {
+                let along = |step: isize| (x as isize + dx * step, y as isize + dy * step);
+                let is_open = |(nx, ny): (isize, isize)| board.in_bounds_signed(nx, ny) && open.contains(&(nx as usize, ny as usize));
+                if !is_open(along(1)) && !is_open(along(-1)) {
+                    continue;
+                }
+
+                for dir in [1, -1] {
+                    let mut step = dir;
+                    while is_open(along(step)) {
+                        step += dir;
+                    }
+                    let (nx, ny) = along(step);
+                    if is_unknown(board, nx, ny) && !line.contains(&(nx as usize, ny as usize)) {
+                        line.push((nx as usize, ny as usize));
+                    }
+                }
+            }
+        }
+        if !line.is_empty() {
+            return line;
+        }
+
+        // Otherwise any untried neighbour of a hit
+        let mut neighbours = Vec::new();
+        for &(x, y) in &open {
+            for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if is_unknown(board, nx, ny) && !neighbours.contains(&(nx as usize, ny as usize)) {
+                    neighbours.push((nx as usize, ny as usize));
+                }
+            }
+        }
+        neighbours
+    }
+
+    /// Untried cells on the checkerboard for the smallest ship left, or every untried cell if none are left on it.
+    pub(crate) fn hunt_cells(&self, game: &Game, player: &BasePlayer) -> Vec<(usize, usize)> {
+        let board = &player.guess_board;
+        let spacing = remaining_lengths(game, player).into_iter().min().unwrap_or(1);
+
+        let unknown: Vec<(usize, usize)> = (0..board.height)
+            .flat_map(|x| (0..board.width).map(move |y| (x, y)))
+            .filter(|&(x, y)| is_unknown(board, x as isize, y as isize))
+            .collect();
+        let spaced: Vec<(usize, usize)> = unknown.iter().copied().filter(|&(x, y)| (x + y) % spacing == 0).collect();
+
+        if spaced.is_empty() { unknown } else { spaced }
+    }
+}
+
+impl ProbabilityDensity {
+    /// Creates the opponent, the same seed always makes the same choices in the same game.
+    pub fn new(seed: u64) -> Self {
+        ProbabilityDensity {
+            rng: StdRng::seed_from_u64(seed),
+            ship_heat: None,
+        }
+    }
+}
+
+impl Peeking {
+    /// Creates the opponent, the same seed always makes the same choices in the same game.
+    pub fn new(seed: u64) -> Self {
+        Peeking {
+            density: ProbabilityDensity::new(seed),
+            // A different stream from the density player's so the two don't move in step
+            rng: StdRng::seed_from_u64(seed.wrapping_add(1)),
+        }
+    }
+}
+
+impl CardPlayer {
+    /// Plays cards on top of `targeting`, the same seed always makes the same choices in the same game.
+    pub fn new(targeting: Box<dyn Opponent>, seed: u64) -> Self {
+        CardPlayer {
+            targeting,
+            // Kept apart from the streams the wrapped opponents seed from `seed` and `seed + 1`
+            rng: StdRng::seed_from_u64(seed.wrapping_add(2)),
+        }
+    }
+
+    /// Expected hits from playing `action`. `chances` is our view of the opponent's board,
+    /// `threat` is the opponent's view of ours.
+    pub(crate) fn score(game: &Game, id: PlayerId, action: Action, chances: &[Vec<f64>], threat: &[Vec<f64>]) -> f64 {
+        let player = game.player(id);
+        let guess = &player.base.guess_board;
+
+        match action {
+            Action::Torpedo { col } => torpedo_chance(guess, col, chances),
+            Action::RadarScan { x, y } => {
+                let mut found = 0.0;
+                for (dx, dy) in [(0, 0), (0, 1), (0, -1), (1, 0), (-1, 0)] {
+                    let (nx, ny) = (x as isize + dx, y as isize + dy);
+                    if guess.in_bounds_signed(nx, ny) && matches!(guess.cells[nx as usize][ny as usize], Cells::Empty | Cells::Miss) {
+                        found += chances[nx as usize][ny as usize];
+                    }
+                }
+                RADAR_WEIGHT * found
+            }
+            Action::Reinforce { x, y } => REINFORCE_WEIGHT * threat[x][y],
+            Action::Patrol { ship, dir } => {
+                let (dx, dy) = dir.delta();
+                let Ok(to) = player.patrol_destination(ship, dx, dy) else {
+                    return f64::NEG_INFINITY;
+                };
+                // Moving off cells the opponent has found (or is closing in on) onto ones it has ruled out
+                let from: f64 = player.base.ships[ship].positions.iter().map(|&(x, y)| threat[x][y]).sum();
+                let to: f64 = to.iter().map(|&(x, y)| threat[x][y]).sum();
+                PATROL_WEIGHT * (from - to)
+            }
+            Action::Missile { x, y } => chances[x][y],
+            Action::EndTurn | Action::Resign => 0.0,
+        }
+    }
+}
+
+impl Advice {
+    /// Works out the advice for `id` in the game as it stands.
+    pub fn new(game: &Game, id: PlayerId) -> Self {
+        let player = game.player(id);
+        let guess = &player.base.guess_board;
+        let chances = hit_chances(game, id);
+
+        // The first of equally likely cells, so the outline stays put from one frame to the next
+        let mut target = None;
+        let mut best = f64::NEG_INFINITY;
+        for (x, row) in chances.iter().enumerate() {
+            for (y, &chance) in row.iter().enumerate() {
+                if !matches!(guess.cells[x][y], Cells::Hit | Cells::Miss) && chance > best {
+                    best = chance;
+                    target = Some((x, y));
+                }
+            }
+        }
+
+        let mut torpedo = None;
+        if game.rules.mode == Mode::Twist && player.hand.contains(&ActionType::Torpedo) {
+            let mut best = f64::NEG_INFINITY;
+            for col in 0..guess.width {
+                let chance = torpedo_chance(guess, col, &chances);
+                if chance > best {
+                    best = chance;
+                    torpedo = Some(col);
+                }
+            }
+        }
+
+        Advice { chances, target, torpedo }
+    }
+}
+
+/* ------ Trait Implementations ------ */
+impl Opponent for RandomShots {
+    fn choose_action(&mut self, game: &Game, id: PlayerId) -> Action {
+        let legal = game.legal_actions(id);
+        let missiles: Vec<Action> = legal.iter().copied().filter(|action| matches!(action, Action::Missile { .. })).collect();
+
+        missiles.choose(&mut self.rng)
+            .or_else(|| legal.choose(&mut self.rng))
+            .copied()
+            .unwrap_or(Action::EndTurn)
+    }
+}
+
+impl Opponent for HuntTarget {
+    fn choose_action(&mut self, game: &Game, id: PlayerId) -> Action {
+        let legal = game.legal_actions(id);
+        let player = &game.player(id).base;
+
+        let mut cells = self.target_cells(player);
+        if cells.is_empty() {
+            cells = self.hunt_cells(game, player);
+        }
+        cells.shuffle(&mut self.rng);
+
+        for (x, y) in cells {
+            let action = Action::Missile { x, y };
+            if legal.contains(&action) {
+                return action;
+            }
+        }
+        // No missile to fire (the turn is over, or a Twist hand without one), take anything that's allowed
+        legal.choose(&mut self.rng).copied().unwrap_or(Action::EndTurn)
+    }
+}
+
+impl Opponent for ProbabilityDensity {
+    fn choose_action(&mut self, game: &Game, id: PlayerId) -> Action {
+        let legal = game.legal_actions(id);
+        let density = probability_density(game, id);
+
+        // Highest scoring cell that can still be fired at, ties broken at random
+        let mut best = Vec::new();
+        let mut best_score = f64::NEG_INFINITY;
+        for &action in &legal {
+            if let Action::Missile { x, y } = action {
+                let habit = self.ship_heat.as_ref().map_or(0.0, |heat| heat[x][y]);
+                let score = density[x][y] * (1.0 + HABIT_WEIGHT * habit);
+                if score > best_score {
+                    best_score = score;
+                    best.clear();
+                }
+                if score == best_score {
+                    best.push(action);
+                }
+            }
+        }
+
+        best.choose(&mut self.rng)
+            .or_else(|| legal.choose(&mut self.rng))
+            .copied()
+            .unwrap_or(Action::EndTurn)
+    }
+
+    fn study(&mut self, ship_heat: &[Vec<f64>]) {
+        self.ship_heat = Some(ship_heat.to_vec());
+    }
+}
+
+impl Opponent for Peeking {
+    fn choose_action(&mut self, game: &Game, id: PlayerId) -> Action {
+        let hunting = open_hits(&game.player(id).base).is_empty();
+        if hunting && self.rng.random_bool(PEEK_CHANCE) {
+            // The cheat: pick a ship cell straight off the opponent's board
+            let board = &game.player(id.other()).base.board;
+            let legal = game.legal_actions(id);
+            let ship_cells: Vec<Action> = legal.into_iter()
+                .filter(|action| match *action {
+                    Action::Missile { x, y } => matches!(board.cells[x][y], Cells::Occupied | Cells::Reinforced),
+                    _ => false,
+                })
+                .collect();
+            if let Some(&action) = ship_cells.choose(&mut self.rng) {
+                return action;
+            }
+        }
+        self.density.choose_action(game, id)
+    }
+
+    fn study(&mut self, ship_heat: &[Vec<f64>]) {
+        self.density.study(ship_heat);
+    }
+}
+
+impl Opponent for CardPlayer {
+    fn choose_action(&mut self, game: &Game, id: PlayerId) -> Action {
+        let legal = game.legal_actions(id);
+        let chances = hit_chances(game, id);
+        let threat = hit_chances(game, id.other());
+
+        let mut best = None;
+        let mut best_score = f64::NEG_INFINITY;
+
+        // Any missile is worth the best cell on offer, the wrapped opponent decides which cell that is
+        for &action in &legal {
+            if let Action::Missile { x, y } = action {
+                best_score = best_score.max(chances[x][y]);
+            }
+        }
+        if best_score > f64::NEG_INFINITY {
+            best = Some(self.targeting.choose_action(game, id));
+        }
+
+        // A card has to beat the missile outright, on a tie the missile is fired and the card kept for later
+        let mut cards: Vec<Action> = legal.iter().copied()
+            .filter(|action| !matches!(action, Action::Missile { .. } | Action::EndTurn))
+            .collect();
+        cards.shuffle(&mut self.rng);
+        for action in cards {
+            let score = CardPlayer::score(game, id, action, &chances, &threat);
+            if best.is_none() || score > best_score {
+                best_score = score;
+                best = Some(action);
+            }
+        }
+
+        best.unwrap_or(Action::EndTurn)
+    }
+
+    fn join(&mut self, game: &mut Game, id: PlayerId) {
+        self.targeting.join(game, id);
+    }
+
+    fn study(&mut self, ship_heat: &[Vec<f64>]) {
+        self.targeting.study(ship_heat);
+    }
+}
+
+/* ------ Functions ------ */
+/// Returns true if (x, y) is on the board and hasn't been hit or missed yet.
+fn is_unknown(board: &Board, x: isize, y: isize) -> bool {
+    board.in_bounds_signed(x, y) && !matches!(board.cells[x as usize][y as usize], Cells::Hit | Cells::Miss)
+}
+
+/// For every cell of the opponent's board, how many placements of `id`'s unsunk targets could cover it.
+/// Only uses what `id` knows: misses, water seen by radar and sunk ships rule placements out (and the water
+/// around sunk ships when ships can't touch), and while a damaged ship is afloat placements through its hits count
+/// far more, so the highest cell is where the next shot is most likely to hit.
+/// Cells already fired at score 0, misses only while no patrolled ship could be hiding under one.
+pub fn probability_density(game: &Game, id: PlayerId) -> Vec<Vec<f64>> {
+    let player = &game.player(id).base;
+    let board = &player.guess_board;
+    let (height, width) = (board.height, board.width);
+
+    // Cells known to hold an unsunk ship, hits and ship cells a radar scan revealed
+    let mut known = vec![vec![false; width]; height];
+    for (x, y) in open_hits(player) {
+        known[x][y] = true;
+    }
+    for (known_row, row) in known.iter_mut().zip(&board.cells) {
+        for (known_cell, cell) in known_row.iter_mut().zip(row) {
+            if matches!(cell, Cells::Occupied | Cells::Reinforced) {
+                *known_cell = true;
+            }
+        }
+    }
+    let any_known = known.iter().flatten().any(|&cell| cell);
+
+    // Hits that no layout explains mean a ship has patrolled onto water seen before, so stop trusting
+    // misses radar hasn't checked since, and if that isn't enough the radar scans too, oldest first
+    let lengths = remaining_lengths(game, player);
+    let scanned = &game.player(id).scanned_cells;
+    let mut density = Vec::new();
+    let mut stale = false;
+    for (trust_misses, oldest) in std::iter::once((true, 0)).chain((0..=scanned.len()).map(|oldest| (false, oldest))) {
+        let blocked = blocked_cells(game, id, trust_misses, &scanned[oldest..]);
+        let (counted, targeting) = count_placements(&lengths, &blocked, &known);
+        density = counted;
+        if targeting || !any_known {
+            break;
+        }
+        stale = true;
+    }
+
+    // Only cells that haven't been fired at are worth anything
+    for (density_row, row) in density.iter_mut().zip(&board.cells) {
+        for (density_cell, &cell) in density_row.iter_mut().zip(row) {
+            if cell == Cells::Hit || (cell == Cells::Miss && !stale) {
+                *density_cell = 0.0;
+            }
+        }
+    }
+    density
+}
+
+/// Cells of the opponent's board `id` believes no unsunk ship is on: sunk ships (and the water around them
+/// when ships can't touch), misses if `trust_misses` and water radar saw in the `scans` it trusts.
+pub(crate) fn blocked_cells(game: &Game, id: PlayerId, trust_misses: bool, scans: &[(usize, usize)]) -> Vec<Vec<bool>> {
+    let player = &game.player(id).base;
+    let board = &player.guess_board;
+    let (height, width) = (board.height, board.width);
+
+    let mut blocked: Vec<Vec<bool>> = board.cells.iter()
+        .map(|row| row.iter().map(|&cell| trust_misses && cell == Cells::Miss).collect())
+        .collect();
+    for &(x, y) in scans {
+        if matches!(board.cells[x][y], Cells::Empty | Cells::Miss) {
+            blocked[x][y] = true;
+        }
+    }
+    for ship in &player.sunk_ships {
+        for &(x, y) in &ship.positions {
+            blocked[x][y] = true;
+            if game.rules.no_touching {
+                for row in &mut blocked[x.saturating_sub(1)..=(x + 1).min(height - 1)] {
+                    row[y.saturating_sub(1)..=(y + 1).min(width - 1)].fill(true);
+                }
+            }
+        }
+    }
+    blocked
+}
+
+/// Counts the placements of each of `lengths` that miss every `blocked` cell, for every cell they cover.
+/// Placements through a `known` ship cell are counted separately and take over once there are any,
+/// the returned flag says whether they did.
+fn count_placements(lengths: &[usize], blocked: &[Vec<bool>], known: &[Vec<bool>]) -> (Vec<Vec<f64>>, bool) {
+    let (height, width) = (blocked.len(), blocked[0].len());
+    let mut hunt = vec![vec![0.0; width]; height];
+    let mut target = vec![vec![0.0; width]; height];
+    let mut targeting = false;
+
+    for &length in lengths {
+        for horizontal in [true, false] {
+            if length == 1 && !horizontal {
+                continue; // A single cell ship is the same both ways round
+            }
+            let (rows, cols) = if horizontal {
+                (height, (width + 1).saturating_sub(length))
+            } else {
+                ((height + 1).saturating_sub(length), width)
+            };
+
+            for x in 0..rows {
+                for y in 0..cols {
+                    let cells: Vec<(usize, usize)> = (0..length)
+                        .map(|i| if horizontal { (x, y + i) } else { (x + i, y) })
+                        .collect();
+                    if cells.iter().any(|&(cx, cy)| blocked[cx][cy]) {
+                        continue;
+                    }
+
+                    let explained = cells.iter().filter(|&&(cx, cy)| known[cx][cy]).count();
+                    for &(cx, cy) in &cells {
+                        hunt[cx][cy] += 1.0;
+                        if explained > 0 {
+                            target[cx][cy] += TARGET_WEIGHT.powi(explained as i32);
+                            targeting = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if targeting { (target, true) } else { (hunt, false) }
+}
+
+/// Rough chance each cell of the opponent's board holds a ship, from `id`'s point of view.
+/// Ship cells `id` can see count as certain, the ship cells still to be found are shared out
+/// over the rest in proportion to `probability_density`. If no layout fits what `id` has seen,
+/// a patrol has hidden a ship under an old miss, so the misses radar hasn't checked share them out instead.
+pub fn hit_chances(game: &Game, id: PlayerId) -> Vec<Vec<f64>> {
+    let player = &game.player(id).base;
+    let board = &player.guess_board;
+    let mut chances = probability_density(game, id);
+
+    let mut known = open_hits(player).len();
+    for (chance_row, row) in chances.iter_mut().zip(&board.cells) {
+        for (chance, cell) in chance_row.iter_mut().zip(row) {
+            if matches!(cell, Cells::Occupied | Cells::Reinforced) {
+                *chance = 0.0;
+                known += 1;
+            }
+        }
+    }
+    let unfound = remaining_lengths(game, player).iter().sum::<usize>().saturating_sub(known) as f64;
+    let total: f64 = chances.iter().flatten().sum();
+    // With nowhere left for the unfound ships they must have patrolled onto water that was already missed
+    // (ones radar hasn't checked, unless a ship has since moved onto those too)
+    let scanned = &game.player(id).scanned_cells;
+    let cells = || (0..board.height).flat_map(|x| (0..board.width).map(move |y| (x, y)));
+    let trust_scans = cells().any(|(x, y)| board.cells[x][y] == Cells::Miss && !scanned.contains(&(x, y)));
+    let stale = |x: usize, y: usize| board.cells[x][y] == Cells::Miss && !(trust_scans && scanned.contains(&(x, y)));
+    let misses = cells().filter(|&(x, y)| stale(x, y)).count() as f64;
+
+    for (x, row) in chances.iter_mut().enumerate() {
+        for (y, chance) in row.iter_mut().enumerate() {
+            *chance = match board.cells[x][y] {
+                Cells::Occupied | Cells::Reinforced => 1.0,
+                _ if total > 0.0 => (*chance * unfound / total).min(1.0),
+                _ if stale(x, y) => (unfound / misses).min(1.0),
+                _ => 0.0,
+            };
+        }
+    }
+    chances
+}
+
+/// Chance a torpedo up `col` hits a ship, given the chance each cell holds one.
+fn torpedo_chance(guess: &Board, col: usize, chances: &[Vec<f64>]) -> f64 {
+    // Travels up from the bottom through water until it reaches something
+    let mut reach = 1.0; // Chance it gets this far without hitting anything
+    let mut score = 0.0;
+    for x in (0..guess.height).rev() {
+        match guess.cells[x][col] {
+            // Misses are only worth a radar scan, a torpedo would find them the same as last time
+            Cells::Miss => continue,
+            Cells::Empty => {
+                score += reach * chances[x][col];
+                reach *= 1.0 - chances[x][col];
+            }
+            Cells::Occupied => {
+                score += reach;
+                break;
+            }
+            // Stops on an old hit for nothing, or only strips a reinforcement
+            Cells::Hit | Cells::Reinforced => break,
+        }
+    }
+    score
+}
+
+/// Hits on the guess board that aren't part of a ship already sunk.
+pub fn open_hits(player: &BasePlayer) -> Vec<(usize, usize)> {
+    let board = &player.guess_board;
+    let mut hits = Vec::new();
+    for x in 0..board.height {
+        for y in 0..board.width {
+            let sunk = player.sunk_ships.iter().any(|ship| ship.positions.contains(&(x, y)));
+            if board.cells[x][y] == Cells::Hit && !sunk {
+                hits.push((x, y));
+            }
+        }
+    }
+    hits
+}
+
+/// Lengths of the opponent's ships that `player` hasn't sunk yet, worked out from the fleet in the rules.
+pub fn remaining_lengths(game: &Game, player: &BasePlayer) -> Vec<usize> {
+    let mut lengths: Vec<usize> = game.rules.fleet.entries.iter()
+        .flat_map(|entry| std::iter::repeat_n(entry.length, entry.count))
+        .collect();
+    for ship in &player.sunk_ships {
+        if let Some(i) = lengths.iter().position(|&length| length == ship.positions.len()) {
+            lengths.remove(i);
+        }
+    }
+    lengths
+}
+
+/* ------ Tests ------ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::Direction;
+    use crate::game::Phase;
+    use crate::rules::{Fleet, FleetEntry, Rules};
+    use crate::twist::HAND_SIZE;
+
+    /// A started game on a 5x5 board where each player has a single Cruiser
+    fn small_game(mode: Mode, seed: u64) -> Game {
+        let fleet = Fleet::new().with(FleetEntry::new(ShipType::Cruiser, 3, 1));
+        let mut game = Game::with_seed(Rules::new(mode).with_board(5, 5).with_fleet(fleet), seed).unwrap();
+        game.start();
+        game
+    }
+
+    /// Marks `cells` of Player 2's fleet as hit by Player 1, as if they had fired at them on earlier turns
+    fn hit(game: &mut Game, cells: &[(usize, usize)]) {
+        for &(x, y) in cells {
+            game.players[0].base.guess_board.cells[x][y] = Cells::Hit;
+            game.players[1].base.board.cells[x][y] = Cells::Hit;
+        }
+    }
+
+    #[test]
+    fn hunt_target_works_the_neighbours_of_a_hit() {
+        for seed in 0..10 {
+            let mut game = small_game(Mode::Classic, seed);
+            let (x, y) = game.player(PlayerId::Two).base.ships[0].positions[1];
+            hit(&mut game, &[(x, y)]);
+
+            let Action::Missile { x: tx, y: ty } = HuntTarget::new(seed).choose_action(&game, PlayerId::One) else {
+                panic!("no missile fired with seed {}", seed);
+            };
+            assert_eq!(x.abs_diff(tx) + y.abs_diff(ty), 1, "({}, {}) isn't next to ({}, {})", tx, ty, x, y);
+        }
+    }
+
+    #[test]
+    fn hunt_target_follows_a_line_of_hits() {
+        for seed in 0..10 {
+            let mut game = small_game(Mode::Classic, seed);
+            let ship = game.player(PlayerId::Two).base.ships[0].positions.clone();
+            hit(&mut game, &ship[..2]);
+
+            // The cell past either end of the two hits, along the ship
+            let (dx, dy) = (ship[1].0 as isize - ship[0].0 as isize, ship[1].1 as isize - ship[0].1 as isize);
+            let ends: Vec<Action> = [(ship[1], 1), (ship[0], -1)].into_iter()
+                .map(|((x, y), dir)| (x as isize + dx * dir, y as isize + dy * dir))
+                .filter(|&(x, y)| (0..5).contains(&x) && (0..5).contains(&y))
+                .map(|(x, y)| Action::Missile { x: x as usize, y: y as usize })
+                .collect();
+            let action = HuntTarget::new(seed).choose_action(&game, PlayerId::One);
+            assert!(ends.contains(&action), "{:?} isn't on the line of hits with seed {}", action, seed);
+        }
+    }
+
+    #[test]
+    fn hunt_target_hunts_on_a_checkerboard() {
+        // Nothing shorter than a Cruiser is left, so every third cell is enough to find it
+        let game = small_game(Mode::Classic, 1);
+        let mut hunter = HuntTarget::new(1);
+        for _ in 0..10 {
+            let Action::Missile { x, y } = hunter.choose_action(&game, PlayerId::One) else {
+                panic!("no missile fired");
+            };
+            assert_eq!((x + y) % 3, 0);
+        }
+    }
+
+    #[test]
+    fn density_is_highest_in_the_middle() {
+        let game = small_game(Mode::Classic, 1);
+        let density = probability_density(&game, PlayerId::One);
+        // A Cruiser fits over the middle cell six ways and over a corner two
+        assert_eq!(density[2][2], 3.0 * density[0][0]);
+        assert!(density.iter().flatten().all(|&cell| cell <= density[2][2]));
+    }
+
+    #[test]
+    fn density_rules_out_cells_already_fired_at() {
+        let mut game = small_game(Mode::Classic, 1);
+        game.players[0].base.guess_board.cells[2][2] = Cells::Miss;
+        let density = probability_density(&game, PlayerId::One);
+        assert_eq!(density[2][2], 0.0);
+        // The Cruiser can no longer lie across the middle, so the cells beside it lose those placements
+        assert!(density[2][1] < density[1][1]);
+    }
+
+    #[test]
+    fn density_fires_in_line_with_an_open_hit() {
+        for seed in 0..10 {
+            let mut game = small_game(Mode::Classic, seed);
+            let (x, y) = game.player(PlayerId::Two).base.ships[0].positions[0];
+            hit(&mut game, &[(x, y)]);
+
+            let Action::Missile { x: tx, y: ty } = ProbabilityDensity::new(seed).choose_action(&game, PlayerId::One) else {
+                panic!("no missile fired with seed {}", seed);
+            };
+            assert!(x.abs_diff(tx) + y.abs_diff(ty) <= 2 && (x == tx || y == ty), "({}, {}) isn't in line with ({}, {})", tx, ty, x, y);
+        }
+    }
+
+    #[test]
+    fn levels_read_back_from_their_names() {
+        for level in Difficulty::ALL {
+            assert_eq!(Difficulty::from_name(&level.name().to_lowercase()), Some(level));
+        }
+        assert_eq!(Difficulty::from_name("impossible"), None);
+    }
+
+    #[test]
+    fn every_level_finishes_a_game() {
+        for level in Difficulty::ALL {
+            let mut game = Game::with_seed(Rules::new(Mode::Classic), 5).unwrap();
+            let mut players = [level.opponent(5), Difficulty::Easy.opponent(6)];
+            game.start();
+            while !matches!(game.phase, Phase::GameOver { .. }) {
+                match game.phase {
+                    Phase::Turn(id) => {
+                        let action = players[if id == PlayerId::One { 0 } else { 1 }].choose_action(&game, id);
+                        game.apply(action).unwrap_or_else(|e| panic!("{} picked {:?}: {}", level.name(), action, e));
+                    }
+                    _ => game.begin_turn(),
+                }
+                assert!(game.turn <= 200, "{} didn't finish", level.name());
+            }
+        }
+    }
+
+    #[test]
+    fn card_player_torpedoes_a_ship_radar_found() {
+        for seed in 0..10 {
+            let mut game = small_game(Mode::Twist, seed);
+            let (x, y) = game.player(PlayerId::Two).base.ships[0].positions[0];
+            game.players[0].base.guess_board.cells[x][y] = Cells::Occupied;
+            game.players[0].hand = vec![ActionType::Torpedo; HAND_SIZE];
+
+            let mut player = CardPlayer::new(Box::new(HuntTarget::new(seed)), seed);
+            assert_eq!(player.choose_action(&game, PlayerId::One), Action::Torpedo { col: y });
+        }
+    }
+
+    #[test]
+    fn radar_only_counts_cells_still_unknown() {
+        let mut game = small_game(Mode::Twist, 1);
+        let chances = vec![vec![0.1; 5]; 5];
+        let scan = Action::RadarScan { x: 2, y: 2 };
+        let score = CardPlayer::score(&game, PlayerId::One, scan, &chances, &chances);
+        assert!((score - RADAR_WEIGHT * 0.5).abs() < 1e-9);
+
+        hit(&mut game, &[(2, 2), (1, 2), (3, 2), (2, 1), (2, 3)]);
+        assert_eq!(CardPlayer::score(&game, PlayerId::One, scan, &chances, &chances), 0.0);
+    }
+
+    #[test]
+    fn blocked_patrols_are_never_worth_playing() {
+        let game = small_game(Mode::Twist, 1);
+        let chances = vec![vec![0.1; 5]; 5];
+        for dir in [Direction::Up, Direction::Down, Direction::Left, Direction::Right] {
+            let (dx, dy) = dir.delta();
+            let blocked = game.player(PlayerId::One).patrol_destination(0, dx, dy).is_err();
+            let score = CardPlayer::score(&game, PlayerId::One, Action::Patrol { ship: 0, dir }, &chances, &chances);
+            assert_eq!(score == f64::NEG_INFINITY, blocked, "{:?}", dir);
+        }
+    }
+
+    #[test]
+    fn card_players_finish_twist_games() {
+        for seed in 0..3 {
+            let mut game = Game::with_seed(Rules::new(Mode::Twist), seed).unwrap();
+            let mut players = [
+                CardPlayer::new(Box::new(HuntTarget::new(seed)), seed),
+                CardPlayer::new(Box::new(ProbabilityDensity::new(seed + 1)), seed + 1),
+            ];
+            game.start();
+            while !matches!(game.phase, Phase::GameOver { .. }) {
+                match game.phase {
+                    Phase::Turn(id) => {
+                        let action = players[if id == PlayerId::One { 0 } else { 1 }].choose_action(&game, id);
+                        game.apply(action).unwrap_or_else(|e| panic!("{:?} was refused: {}", action, e));
+                    }
+                    _ => game.begin_turn(),
+                }
+                assert!(game.turn <= 1_000, "the game with seed {} didn't finish", seed);
+            }
+        }
+    }
+}
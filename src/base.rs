@@ -1,22 +1,28 @@
 //! Core game logic for Battleship Classic mode.
 //! Handles board states, ship placement, and basic attack mechanics.
+//! Nothing in here draws to the screen, `ui.rs` reads these states and colours the grids.
 
 /* ------ Import Used Libraries ------ */
-// Graphics library
-use macroquad::prelude::*;
 // Random library
-use ::rand::prelude::*;
-// A module I recompiled and made small fixes to, but did not write. Used for grid graphics and logic.
-// Origional code avaliable here: https://github.com/TheDinner22/macroquad_grid
-extern crate macroquad_grid_dex; 
-use macroquad_grid_dex::Grid;
+use ::rand::Rng;
+// Errors returned by actions
+use crate::error::{ActionError, RulesError};
+// Feedback sent to the ui and anything else listening
+use crate::events::{EventBus, GameEvent};
+// Board size and fleet
+use crate::placement::{place_fleet, Strategy};
+use crate::rules::Rules;
+use crate::twist::ActionType;
+use std::fmt;
+// Saving and loading
+use serde::{Deserialize, Serialize};
 
 /*------ Constants ------ */
-pub const GRID_SIZE: usize = 10;    // Defines how many cells make up a grid
+pub const GRID_SIZE: usize = 10;    // Default number of cells along each side of a grid
 
 /*------ Enums and Structs ------ */
 /// Represents possible states of a grid cell.
-#[derive(Copy, Clone, PartialEq)] // Copy - Enables bitwise copying of the type, doesn't move ownership. Clone - Creates a deep copy of the value, can proform complex copying. PartialEq - Allows comparison of this type.
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)] // Copy - Enables bitwise copying of the type, doesn't move ownership. Clone - Creates a deep copy of the value, can proform complex copying. PartialEq - Allows comparison of this type. Serialize/Deserialize - Allows saving and loading.
 pub enum Cells {
     Empty,      // Unknow or empty cell
     Occupied,   // Cell containing part of a ship
@@ -26,74 +32,174 @@ pub enum Cells {
 }
 
 /// Game board containing cell's and their states
+#[derive(Clone, Serialize, Deserialize)] // Clone - Creates a deep copy of the value, so a search can play on a copy of the game. Serialize/Deserialize - Allows saving and loading
 pub struct Board {
-    pub cells: [[Cells; GRID_SIZE]; GRID_SIZE] 
+    pub width: usize,          // Number of columns
+    pub height: usize,         // Number of rows
+    pub cells: Vec<Vec<Cells>>, // Indexed as cells[row][column], `height` rows of `width` cells
 }
 
 /// Orientation for ship placement.
-#[derive(Clone)] // Clone - Creates a deep copy of the value, can proform complex copying.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)] // Copy - Enables bitwise copying of the type. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}. Serialize/Deserialize - Allows saving and loading
 pub enum Orientation {
     Horizontal, // The ship is horizontal 
     Verticle,   // The ship is verticle 
 }
 
-/// Types of ships and their respective lengths.
-#[derive(Clone)] // Clone - Creates a deep copy of the value, can proform complex copying.
+/// Types of ships, their lengths come from the fleet in the rules.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)] // Copy - Enables bitwise copying of the type. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}. Serialize/Deserialize - Allows saving and loading
 pub enum ShipType {
-    Battleship,  // Ship size 4
-    Cruiser,     // Ship size 3
-    Submarine,   // Ship size 3
-    Destroyer,   // Ship size 2
-    Dreadnaught, // Ship size 5
+    Battleship,  // Size 4 in the classic fleet
+    Cruiser,     // Size 3 in the classic fleet
+    Submarine,   // Size 3 in the classic fleet
+    Destroyer,   // Size 2 in the classic fleet
+    Dreadnaught, // Size 5 in the classic fleet
 }
 
 /// Represents a ship on the board.
-#[derive(Clone)] // Clone - Creates a deep copy of the value, can proform complex copying.
+#[derive(Clone, Serialize, Deserialize)] // Clone - Creates a deep copy of the value, can proform complex copying. Serialize/Deserialize - Allows saving and loading.
 pub struct Ship {
-    pub ship_type: ShipType,            // Tracks the type of ship and determines length
+    pub ship_type: ShipType,            // Tracks the type of ship
+    pub name: String,                   // Name shown to players, from the fleet entry
     pub positions: Vec<(usize, usize)>, // Grid coordinates the ship 
     pub orientation: Orientation,       // Orientation of the ship used for ship generation
 }
 
-/// Tracks which player's turn it is.
-#[derive(PartialEq)] // PartialEq - Allows comparison of this type.
-pub enum GameState {
-    Player1,
-    Player2,
-    Else, // Transition state between turns, intended to allow for passing of the device to the next player.
+/// What a single shot did to the cell it stopped on.
+#[derive(Clone, Copy, PartialEq, Debug)] // Copy - Enables bitwise copying of the type. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}
+pub enum ShotOutcome {
+    Miss,                  // Only water was hit
+    Hit,                   // Hit a ship that is still afloat
+    Sunk(ShipType),        // Hit the last cell of a ship
+    ReinforcementStripped, // Hit a reinforced cell, the cell lost its extra life but the ship wasn't damaged
+    AlreadyHit,            // Stopped on a cell that had already been hit (torpedo only)
+}
+
+/// Result of a missile or torpedo.
+#[derive(Clone, PartialEq, Debug)] // Clone - Creates a deep copy of the value. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}
+pub struct Shot {
+    pub outcome: ShotOutcome,       // What happened where the shot stopped
+    pub cells: Vec<(usize, usize)>, // Every cell the shot touched in order, the last one is where it stopped
+}
+
+/// Identifies each of the two players.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)] // Copy - Enables bitwise copying of the type. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}. Serialize/Deserialize - Allows saving and loading
+pub enum PlayerId {
+    One,
+    Two,
 }
 
 /// Core player structure 
+#[derive(Clone, Serialize, Deserialize)] // Clone - Creates a deep copy of the value, so a search can play on a copy of the game. Serialize/Deserialize - Allows saving and loading
 pub struct BasePlayer {
-    pub board: Board,    // Players own board to see where the opponent guesses and where your ships are.
-    pub boardgrid: Grid, // Visual for the board.
+    pub id: PlayerId, // Which player this is, used to label events
 
+    pub board: Board,       // Players own board to see where the opponent guesses and where your ships are.
     pub guess_board: Board, // Track of opponent's board and your guesses
-    pub guessgrid: Grid,    // Visual of guesses
 
     pub ships: Vec<Ship>,  // Collection of placed ships
     pub ship_count: usize, // Remaining undestroyed ships
+
+    pub sunk_ships: Vec<Ship>, // Opponent ships this player has sunk, so they can be marked on the guess board
 }
 
 /* ------ Struct Implementations ------ */
+/// PlayerId Implementations
+impl PlayerId {
+    /// Returns the opposing player
+    pub fn other(&self) -> PlayerId {
+        match self {
+            PlayerId::One => PlayerId::Two,
+            PlayerId::Two => PlayerId::One,
+        }
+    }
+
+    /// "P1" or "P2", for captions and notation where "Player 1" is too long
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            PlayerId::One => "P1",
+            PlayerId::Two => "P2",
+        }
+    }
+}
+
+impl ShipType {
+    /// Every ship type
+    pub const ALL: [ShipType; 5] = [ShipType::Battleship, ShipType::Cruiser, ShipType::Submarine, ShipType::Destroyer, ShipType::Dreadnaught];
+
+    /// The ship type written as `name` (as shown with {:?}, in any case), None if there isn't one
+    pub fn from_name(name: &str) -> Option<ShipType> {
+        ShipType::ALL.into_iter().find(|ship_type| format!("{:?}", ship_type).eq_ignore_ascii_case(name))
+    }
+
+    /// Letter the ship is drawn with in a board diagram, N for the Dreadnaught as D is the Destroyer
+    pub fn letter(&self) -> char {
+        match self {
+            ShipType::Battleship => 'B',
+            ShipType::Cruiser => 'C',
+            ShipType::Submarine => 'S',
+            ShipType::Destroyer => 'D',
+            ShipType::Dreadnaught => 'N',
+        }
+    }
+
+    /// The ship type drawn as `letter` (upper case), None if there isn't one
+    pub fn from_letter(letter: char) -> Option<ShipType> {
+        ShipType::ALL.into_iter().find(|ship_type| ship_type.letter() == letter)
+    }
+}
+
+/// Shows as "Player 1" or "Player 2"
+impl fmt::Display for PlayerId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlayerId::One => write!(f, "Player 1"),
+            PlayerId::Two => write!(f, "Player 2"),
+        }
+    }
+}
+
+/// Shot Implementations
+impl ShotOutcome {
+    /// Returns true if a ship was damaged.
+    pub fn is_hit(&self) -> bool {
+        matches!(self, ShotOutcome::Hit | ShotOutcome::Sunk(_))
+    }
+}
+
 /// Board Implementations
 impl Board {
-    /// Constructor function
-    pub fn new() -> Self {
+    /// Creates an empty board `width` columns wide and `height` rows tall.
+    pub fn new(width: usize, height: usize) -> Self {
         Board {  
-            cells: [[Cells::Empty; GRID_SIZE]; GRID_SIZE],
+            width,
+            height,
+            cells: vec![vec![Cells::Empty; width]; height],
+        }
+    }
+    /// Returns true if (x, y) is on the board, x is the row and y the column.
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.height && y < self.width
+    }
+
+    /// Returns true if (x, y) is on the board, for positions that may have stepped off the top or left edge.
+    pub fn in_bounds_signed(&self, x: isize, y: isize) -> bool {
+        x >= 0 && y >= 0 && self.in_bounds(x as usize, y as usize)
+    }
+
+    /// Returns an OutOfBounds error if (x, y) isn't on the board.
+    pub fn check_bounds(&self, x: usize, y: usize) -> Result<(), ActionError> {
+        if self.in_bounds(x, y) {
+            Ok(())
+        } else {
+            Err(ActionError::OutOfBounds { x, y })
         }
     }
-    /// Function to change cell based off of the provided celltype and grid position
-    pub fn change_cell(&mut self, x: usize, y: usize, ctype: Cells, grid: &mut Grid) { 
+
+    /// Function to change cell based off of the provided celltype and grid position.
+    /// Hit cells are final and are never overwritten.
+    pub fn change_cell(&mut self, x: usize, y: usize, ctype: Cells) { 
         if self.cells[x][y] != Cells::Hit {
-            match ctype {
-                Cells::Empty => grid.color_cell(x, y, DARKGRAY),
-                Cells::Occupied => grid.color_cell(x, y, GREEN),
-                Cells::Hit => grid.color_cell(x, y, RED),
-                Cells::Miss => grid.color_cell(x, y, GRAY),
-                Cells::Reinforced => grid.color_cell(x,y,DARKGREEN),
-            }
             self.cells[x][y] = ctype;
         }  
     }
@@ -101,152 +207,96 @@ impl Board {
 
 /// Implementation for the base player handles all shared player functions
 impl BasePlayer {
-    /// Player constructor
-    pub fn new() -> Self {
+    /// Player constructor, both boards are sized by the rules and the whole fleet is placed at random
+    /// in the style of `layout`. Fails if the fleet has no layout on the board.
+    pub fn new(id: PlayerId, rules: &Rules, layout: Strategy, rng: &mut (impl Rng + ?Sized)) -> Result<Self, RulesError> {
+        let ships = place_fleet(rules, layout, rng)?;
+        Ok(BasePlayer::with_ships(id, rules, ships))
+    }
+
+    /// Player with boards sized by the rules and `ships` already placed, they are expected to fit the board.
+    pub fn with_ships(id: PlayerId, rules: &Rules, ships: Vec<Ship>) -> Self {
         let mut p = BasePlayer {
-            board: Board::new(),
-            boardgrid: Grid::new(400.0, 400.0, 10, 10, 1.0),
+            id,
 
-            guess_board: Board::new(),
-            guessgrid: Grid::new(400.0, 400.0, 10, 10, 1.0),
+            board: Board::new(rules.width, rules.height),
+            guess_board: Board::new(rules.width, rules.height),
 
-            ships: Vec::new(),
-            ship_count: 5,
+            ship_count: ships.len(),
+            ships,
+
+            sunk_ships: Vec::new(),
         };
 
         //Place ships 
-        p.place_ship(ShipType::Battleship, Orientation::Verticle); 
-        p.place_ship(ShipType::Submarine, Orientation::Verticle);
-        p.place_ship(ShipType::Cruiser, Orientation::Horizontal);
-        p.place_ship(ShipType::Dreadnaught, Orientation::Verticle);
-        p.place_ship(ShipType::Destroyer, Orientation::Horizontal);
-        // Change grid offset and cell colour for your board
-        p.boardgrid.set_x_offset(macroquad_grid_dex::Position::Pixels(150.));
-        p.boardgrid.set_y_offset(macroquad_grid_dex::Position::Pixels(50.));
-        p.boardgrid.set_cell_bg_color(BLACK);
-        p.boardgrid.set_gap_color(LIME);
-        // Change grid offset and cell colour for the guess board
-        p.guessgrid.set_x_offset(macroquad_grid_dex::Position::Pixels(screen_width()-100.));
-        p.guessgrid.set_y_offset(macroquad_grid_dex::Position::Pixels(50.));
-        p.guessgrid.set_cell_bg_color(BLACK);
-        p.guessgrid.set_gap_color(LIME);
-
-        return p;
+        for ship in &p.ships {
+            for &(x, y) in &ship.positions {
+                p.board.cells[x][y] = Cells::Occupied;
+            }
+        }
+
+        p
     }
 
     /// Base attack / guess from the IRL game
-    /// Returns true if attack hits a ship.
-    pub fn fire_missile(&mut self, opponent: &mut BasePlayer, target_x: usize, target_y: usize) -> bool {
-        let ocell = &mut opponent.board.cells[target_x][target_y]; // Create a local refrence to the cell
-        
-        match *ocell {
-            Cells::Occupied => {
-                opponent.board.change_cell(target_x, target_y, Cells::Hit, &mut opponent.boardgrid);
-                self.guess_board.change_cell(target_x, target_y, Cells::Hit, &mut self.guessgrid);
-                println!("Hit!");
-                
-                // Check if this hit destroyed a ship
-                if let Some(ship_idx) = opponent.find_ship_at(target_x, target_y) {
-                    if opponent.is_ship_destroyed(ship_idx) {
-                        println!("Ship completely destroyed!");
-                        opponent.update_ship_count();
-                    }
-                }
-                true
-            }
-            _ => {
-                opponent.board.change_cell(target_x, target_y, Cells::Miss, &mut opponent.boardgrid);
-                self.guess_board.change_cell(target_x, target_y, Cells::Miss, &mut self.guessgrid);
-                println!("Miss!");
-                false
-            }
+    /// Returns which cell was struck and what happened there.
+    /// Fails if the target is off the board or was already hit or missed.
+    pub fn fire_missile(&mut self, opponent: &mut BasePlayer, target_x: usize, target_y: usize, events: &mut EventBus) -> Result<Shot, ActionError> {
+        opponent.board.check_bounds(target_x, target_y)?;
+        if matches!(self.guess_board.cells[target_x][target_y], Cells::Hit | Cells::Miss) {
+            return Err(ActionError::AlreadyTargeted { x: target_x, y: target_y });
         }
+
+        let outcome = self.strike(opponent, target_x, target_y);
+        let shot = Shot { outcome, cells: vec![(target_x, target_y)] };
+        self.report_shot(ActionType::Missile, &shot, events);
+
+        Ok(shot)
     }
 
-    /// Converts mouse position to grid coordinates on guess board.
-    /// Returns Some((x,y)) if the mouse is within the grid.
-    pub fn get_clicked_cell(&self) -> Option<(usize, usize)> {
-        let (mouse_x, mouse_y) = mouse_position();
-        let grid_x_offset = 700.0;
-        let grid_y_offset = 50.0;
-        let cell_size = 40.0;
-        let grid_size_px = cell_size * GRID_SIZE as f32;
-    
-        if mouse_x >= grid_x_offset && mouse_x < grid_x_offset + grid_size_px &&
-           mouse_y >= grid_y_offset && mouse_y < grid_y_offset + grid_size_px {
-            let x = ((mouse_y - grid_y_offset) / cell_size) as usize;
-            let y = ((mouse_x - grid_x_offset) / cell_size) as usize;
-            return Some((x, y));
+    /// Emits the events for a resolved missile or torpedo, including the sinking if there was one.
+    pub fn report_shot(&self, weapon: ActionType, shot: &Shot, events: &mut EventBus) {
+        events.emit(GameEvent::ShotResolved { player: self.id, weapon, shot: shot.clone() });
+
+        if let (ShotOutcome::Sunk(ship_type), Some(ship)) = (shot.outcome, self.sunk_ships.last()) {
+            events.emit(GameEvent::ShipSunk { player: self.id, ship_type, name: ship.name.clone(), positions: ship.positions.clone() });
         }
-    
-        None
     }
 
-    /// Attemps to place a ship randomly on the board.
-    /// Trues up to 100 times to find a valid placement.
-    pub fn place_ship(&mut self, ship_type: ShipType, orientation: Orientation) -> Option<Ship> {
-        let mut rng = ::rand::rng();
-        let ship_length = match ship_type {
-            ShipType::Battleship => 4,
-            ShipType::Cruiser => 3,
-            ShipType::Submarine => 3,
-            ShipType::Destroyer => 2,
-            ShipType::Dreadnaught => 5,
-        };
-        
-        let possible_pos: Vec<usize> = (0..GRID_SIZE).collect();
-        
-        for _ in 0..100 {
-            let tempx = possible_pos.choose(&mut rng);
-            let tempy = possible_pos.choose(&mut rng);
-            let x: usize = *tempx.unwrap();
-            let y: usize = *tempy.unwrap();
-    
-            let mut directions = match orientation {
-                Orientation::Horizontal => vec![(0, 1), (0, -1)],
-                Orientation::Verticle => vec![(1, 0), (-1, 0)],
-            };
-            
-            directions.shuffle(&mut rng);
-            let (dx, dy) = directions[0];
-            let mut positions = vec![];
-            let mut fits = true;
-            let mut temp_x = x;
-            let mut temp_y = y;
-    
-            for _ in 0..ship_length {
-                if temp_x >= GRID_SIZE || temp_y >= GRID_SIZE {
-                    fits = false;
-                    break;
+    /// Resolves a hit on one of the opponents cells and updates both players' boards.
+    /// Shared by missiles and torpedoes:
+    /// - Occupied cells are hit, and the ship is recorded as sunk if that was its last cell
+    /// - Reinforced cells lose their reinforcement
+    /// - Hit cells are left alone
+    /// - Anything else is a miss
+    pub fn strike(&mut self, opponent: &mut BasePlayer, x: usize, y: usize) -> ShotOutcome {
+        match opponent.board.cells[x][y] {
+            Cells::Occupied => {
+                opponent.board.change_cell(x, y, Cells::Hit);
+                self.guess_board.change_cell(x, y, Cells::Hit);
+
+                // Check if this hit destroyed a ship
+                if let Some(ship_idx) = opponent.find_ship_at(x, y) {
+                    if opponent.is_ship_destroyed(ship_idx) {
+                        opponent.update_ship_count();
+                        self.sunk_ships.push(opponent.ships[ship_idx].clone());
+                        return ShotOutcome::Sunk(opponent.ships[ship_idx].ship_type);
+                    }
                 }
-                positions.push((temp_x, temp_y));
-                temp_x = (temp_x as isize + dx) as usize;
-                temp_y = (temp_y as isize + dy) as usize;
-            }
-    
-            if !fits {
-                continue;
+                ShotOutcome::Hit
             }
-    
-            if positions.iter().any(|&(px, py)| self.board.cells[px][py] != Cells::Empty) {
-                continue;
+            Cells::Reinforced => {
+                opponent.board.change_cell(x, y, Cells::Occupied);
+                self.guess_board.change_cell(x, y, Cells::Occupied);
+                ShotOutcome::ReinforcementStripped
             }
-    
-            for &(sx, sy) in &positions {
-                self.board.change_cell(sx, sy, Cells::Occupied, &mut self.boardgrid);
+            Cells::Hit => ShotOutcome::AlreadyHit,
+            _ => {
+                opponent.board.change_cell(x, y, Cells::Miss);
+                self.guess_board.change_cell(x, y, Cells::Miss);
+                ShotOutcome::Miss
             }
-    
-            let ship = Ship {
-                ship_type,
-                positions,
-                orientation,
-            };
-            
-            self.ships.push(ship.clone());
-            return Some(ship);
         }
-    
-        None
     }
 
     /// Returns the index of the ship at the given coordinates, if any
@@ -272,31 +322,3 @@ impl BasePlayer {
             .count();
     }
 }
-
-pub fn draw_help_menu() {
-    // Semi-transparent background
-    draw_rectangle(100.0, 50.0, 1080.0, 620.0, Color::new(0.0, 0.0, 0.0, 0.85));
-    
-    // Help title
-    draw_text("BATTLESHIP HELP", 150.0, 100.0, 40.0, WHITE);
-
-    // Common / shared Controls
-    draw_text("[Common Controls]", 150.0, 150.0, 30.0, YELLOW);
-    draw_text("- Left Click: Fire a missile", 170.0, 190.0, 25.0, WHITE);
-    draw_text("- Space: Confirm action/End turn", 170.0, 220.0, 25.0, WHITE);
-    
-    // Classic Mode
-    draw_text("[Classic Mode]", 150.0, 270.0, 30.0, GREEN);
-    draw_text("- Just click to attack!", 170.0, 310.0, 25.0, WHITE);
-    
-    // Twist Mode
-    draw_text("[Twist Mode]", 150.0, 360.0, 30.0, PURPLE);
-    draw_text("- T: Torpedo (vertical strike)", 170.0, 400.0, 25.0, WHITE);
-    draw_text("- R: Reinforce ship cell", 170.0, 430.0, 25.0, WHITE);
-    draw_text("- S: Radar Scan (reveals the selected cell and those adjacent)", 170.0, 460.0, 25.0, WHITE);
-    draw_text("- P: Patrol (move ships)", 170.0, 490.0, 25.0, WHITE);
-    draw_text("  - After pressing P: Use arrow keys to move", 190.0, 520.0, 25.0, WHITE);
-    
-    // Close help menu
-    draw_text("Press H to close", 150.0, 570.0, 25.0, YELLOW);
-}
\ No newline at end of file
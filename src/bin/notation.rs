@@ -0,0 +1,49 @@
+//! Command line notation converter.
+//! Writes down a recorded game (a replay, or a game saved with Q while it was being recorded) as notation,
+//! or reads notation back into a JSON replay, e.g. `battleships-notation savegame.json game.txt`.
+//! With no output file the notation is printed, so it can be pasted straight into a chat or a bug report.
+
+/* ------ Import Used Libraries ------ */
+use battleships::notation;
+use battleships::replay::Replay;
+use battleships::save::SavedGame;
+
+/*------ Constants ------ */
+const USAGE: &str = "Usage: battleships-notation FILE [OUTPUT]
+  FILE is a replay or a saved game that was being recorded (.json), or notation (.txt).
+  OUTPUT ending in .txt gets notation and anything else a JSON replay, without it the notation is printed";
+
+/*------ Main ------ */
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (input, output) = match &args[..] {
+        [input] => (input, None),
+        [input, output] => (input, Some(output)),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let converted = load(input).and_then(|replay| match output {
+        Some(path) => replay.save(path).map_err(|e| e.to_string()),
+        None => notation::write(&replay).map(|text| print!("{}", text)).map_err(|e| e.to_string()),
+    });
+    if let Err(e) = converted {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/* ------ Functions ------ */
+/// The recording in `path`, whether it is a replay or a saved game with its replay kept in it.
+fn load(path: &str) -> Result<Replay, String> {
+    match Replay::load(path) {
+        Ok(replay) => Ok(replay),
+        Err(not_replay) => match SavedGame::load(path) {
+            Ok(saved) => saved.replay.ok_or(format!("'{}' is a game that wasn't being recorded, so there's nothing to write down", path)),
+            // Neither, so say why it isn't the more usual of the two
+            Err(_) => Err(not_replay.to_string()),
+        },
+    }
+}
@@ -0,0 +1,210 @@
+//! Command line match simulator.
+//! Plays two computer players against each other for many games with no window and prints how each did,
+//! e.g. `battleships-sim hard normal --games 2000 --mode twist`. A player is a difficulty, optionally
+//! with a fleet layout after a colon (`hard:edge`) to try that layout instead of the level's own picks.
+//! `engine:COMMAND` plays an external engine instead (see `engine.rs`), started afresh for every game.
+
+/* ------ Import Used Libraries ------ */
+use battleships::ai::{Difficulty, Opponent};
+use battleships::engine::{Engine, DEFAULT_TIME_LIMIT};
+use battleships::placement::Strategy;
+use battleships::rules::*;
+use battleships::search::Budget;
+use battleships::sim::*;
+use battleships::twist::ActionType;
+use std::time::Duration;
+
+/*------ Constants ------ */
+const DEFAULT_GAMES: usize = 1_000; // Games played when --games isn't given
+const COLUMN_WIDTH: usize = 12;     // Narrowest a player's column in the results gets
+const USAGE: &str = "Usage: battleships-sim PLAYER PLAYER [--games N] [--mode classic|twist] [--board WxH] [--fleet classic|eastern] [--no-touching] [--seed N] [--time-limit MS] [--iterations N | --think-time MS]
+  PLAYER is easy, normal, hard, expert or master, optionally followed by :LAYOUT (random, edge, spread, clustered or anti-density),
+  or engine:COMMAND to play an external engine that gets --time-limit milliseconds a move.
+  Master gets --iterations playouts (2000 unless told otherwise, so a seed replays the same match) or --think-time milliseconds a move";
+
+/*------ Enums and Structs ------ */
+/// How this run was started from the command line
+struct Settings {
+    rules: Rules,          // Mode, board and fleet every game is played with
+    games: usize,          // Games to play
+    seed: u64,             // Seed of the first game
+    entrants: Vec<Entrant>, // The two players
+}
+
+/*------ Main ------ */
+fn main() {
+    let settings = match settings_from_args(std::env::args().skip(1)) {
+        Ok(settings) => settings,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let entrants = [&settings.entrants[0], &settings.entrants[1]];
+    let stats = match run_match(&settings.rules, entrants, settings.games, settings.seed) {
+        Ok(stats) => stats,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    print_report(&settings, &stats);
+}
+
+/* ------ Functions ------ */
+/// Reads the settings from the command line, or says what's wrong with them.
+fn settings_from_args(mut args: impl Iterator<Item = String>) -> Result<Settings, String> {
+    let mut rules = Rules::new(Mode::Classic);
+    let mut games = DEFAULT_GAMES;
+    let mut seed = ::rand::random();
+    let mut time_limit = DEFAULT_TIME_LIMIT;
+    let mut budget = Budget::default();
+    let mut players = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--games" => {
+                let value = value()?;
+                games = value.parse().map_err(|_| format!("Expected --games NUMBER, got '{}'", value))?;
+            }
+            "--mode" => {
+                rules.mode = match value()?.to_ascii_lowercase().as_str() {
+                    "classic" => Mode::Classic,
+                    "twist" => Mode::Twist,
+                    other => return Err(format!("Unknown mode '{}', expected classic or twist", other)),
+                };
+            }
+            "--board" => {
+                let size = value()?;
+                match size.split_once('x').map(|(w, h)| (w.parse(), h.parse())) {
+                    Some((Ok(width), Ok(height))) => rules = rules.with_board(width, height),
+                    _ => return Err(format!("Expected --board WIDTHxHEIGHT, got '{}'", size)),
+                }
+            }
+            "--fleet" => {
+                let name = value()?;
+                let fleet = Fleet::from_name(&name).ok_or(format!("Unknown fleet '{}', expected classic or eastern", name))?;
+                rules = rules.with_fleet(fleet);
+            }
+            "--no-touching" => rules = rules.with_no_touching(true),
+            "--seed" => {
+                let value = value()?;
+                seed = value.parse().map_err(|_| format!("Expected --seed NUMBER, got '{}'", value))?;
+            }
+            "--time-limit" => {
+                let value = value()?;
+                let millis = value.parse().map_err(|_| format!("Expected --time-limit MILLISECONDS, got '{}'", value))?;
+                time_limit = Duration::from_millis(millis);
+            }
+            "--iterations" => {
+                let value = value()?;
+                budget = Budget::Iterations(value.parse().map_err(|_| format!("Expected --iterations NUMBER, got '{}'", value))?);
+            }
+            "--think-time" => {
+                let value = value()?;
+                let millis = value.parse().map_err(|_| format!("Expected --think-time MILLISECONDS, got '{}'", value))?;
+                budget = Budget::Time(Duration::from_millis(millis));
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+            _ => players.push(arg),
+        }
+    }
+
+    if players.len() != 2 {
+        return Err(format!("Expected two players, got {}", players.len()));
+    }
+    // Read after the options so --time-limit and the budget apply wherever they were given
+    let entrants = players.iter().map(|spec| entrant(spec, time_limit, budget)).collect::<Result<_, _>>()?;
+    Ok(Settings { rules, games, seed, entrants })
+}
+
+/// Reads one player such as `hard`, `hard:edge` or `engine:./my_bot --fast`.
+fn entrant(spec: &str, time_limit: Duration, budget: Budget) -> Result<Entrant, String> {
+    if let Some(command) = spec.strip_prefix("engine:") {
+        return engine_entrant(command.to_string(), time_limit);
+    }
+
+    let (level_name, layout_name) = match spec.split_once(':') {
+        Some((level, layout)) => (level, Some(layout)),
+        None => (spec, None),
+    };
+    let level = Difficulty::from_name(level_name).ok_or(format!("Unknown player '{}'", level_name))?;
+
+    let layout: Box<dyn Fn(u64) -> Strategy> = match layout_name {
+        Some(name) => {
+            let strategy = Strategy::from_name(name).ok_or(format!("Unknown layout '{}'", name))?;
+            Box::new(move |_| strategy)
+        }
+        None => Box::new(move |seed| level.layout(seed)),
+    };
+
+    Ok(Entrant {
+        name: spec.to_string(),
+        player: Box::new(move |seed| level.opponent_with_budget(seed, budget)),
+        layout,
+    })
+}
+
+/// An external engine, started once here to check it works and name it.
+/// Engines lay their fleets out at random.
+fn engine_entrant(command: String, time_limit: Duration) -> Result<Entrant, String> {
+    let name = Engine::spawn(&command, time_limit).map_err(|e| e.to_string())?.name().to_string();
+
+    let player = move |_| -> Box<dyn Opponent> {
+        match Engine::spawn(&command, time_limit) {
+            Ok(engine) => Box::new(engine),
+            // It started fine before, so there's no sensible way to carry on without it
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    };
+    Ok(Entrant { name, player: Box::new(player), layout: Box::new(|_| Strategy::Random) })
+}
+
+/// Prints the results as a table with a column per player.
+fn print_report(settings: &Settings, stats: &MatchStats) {
+    let rules = &settings.rules;
+    println!(
+        "{:?} on a {}x{} board, {} ships{}, {} games from seed {}",
+        rules.mode, rules.width, rules.height, rules.fleet.ship_count(),
+        if rules.no_touching { " that can't touch" } else { "" },
+        stats.games, settings.seed,
+    );
+    if stats.unfinished > 0 {
+        println!("{} games were stopped unfinished", stats.unfinished);
+    }
+    println!();
+
+    let [first, second] = &stats.sides;
+    let names = [&settings.entrants[0].name, &settings.entrants[1].name];
+    let width = names.iter().map(|name| name.len()).max().unwrap_or(0).max(COLUMN_WIDTH) + 2;
+    let row = |label: &str, show: &dyn Fn(&SideStats) -> String| {
+        println!("{:<22}{:>width$}{:>width$}", label, show(first), show(second));
+    };
+    let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+
+    println!("{:<22}{:>width$}{:>width$}", "", names[0], names[1]);
+    row("Wins", &|side| side.wins.to_string());
+    row("Win rate", &|side| format!("{:.1}%", side.win_rate(stats.games) * 100.0));
+    row("Mean turns to win", &|side| or_dash(side.mean_turns().map(|turns| format!("{:.1}", turns))));
+    for percent in [10, 50, 90] {
+        row(&format!("{}th percentile turns", percent), &|side| or_dash(side.percentile_turns(percent as f64).map(|turns| turns.to_string())));
+    }
+    row("Shots per sink", &|side| or_dash(side.shots_per_sink().map(|shots| format!("{:.2}", shots))));
+    if first.forfeits + second.forfeits > 0 {
+        row("Forfeits", &|side| side.forfeits.to_string());
+    }
+
+    if rules.mode == Mode::Twist {
+        println!();
+        println!("Cards played per game");
+        for card in [ActionType::Missile, ActionType::Torpedo, ActionType::RadarScan, ActionType::Reinforce, ActionType::Patrol] {
+            row(&format!("  {:?}", card), &|side| format!("{:.1}", side.card_count(card) as f64 / stats.games.max(1) as f64));
+        }
+    }
+}
@@ -0,0 +1,357 @@
+//! Boards drawn as plain text, one line per row and one character per cell, for test positions, puzzles and
+//! sharing a favourite layout:
+//!
+//! ```text
+//! ..........
+//! .BBBB.....
+//! ........C.
+//! ..N.....#.
+//! ..N.....C.
+//! ..#.......
+//! ..N..SSS..
+//! ..N.......
+//! ......DD..
+//! ..........
+//! ```
+//!
+//! `.` is water, a ship is drawn with its type's letter (`ShipType::letter`) and `#` is a reinforced ship cell.
+//! Spaces between cells and blank lines are ignored. A `#` takes the letter of the ship it sits in, so reading
+//! a drawn fleet needs one cell of each ship left unreinforced, unless the ship's length alone says which it is.
+//! Ships of the same type can touch (when the rules allow it) as long as there is only one way to split them up,
+//! two Destroyers side by side in a square could just as well be two Destroyers across it so they can't be read.
+//!
+//! Boards from a game in progress are written the same way, with a few more characters for what has happened:
+//! a lower case letter is a hit on that ship, `o` is a miss, `x` is a hit on a ship not yet identified and
+//! `+` is a ship cell found by a radar scan. A board with nothing fired at it reads back as a fleet.
+
+/* ------ Import Used Libraries ------ */
+use crate::action::cell_name;
+use crate::base::{BasePlayer, Board, Cells, Ship, ShipType};
+use crate::error::{FileError, RulesError};
+use crate::placement::{fleet_from_ends, ShipEnds};
+use crate::rules::Rules;
+
+/*------ Constants ------ */
+const WATER: char = '.';       // Empty cell, or one nothing is known about
+const REINFORCED: char = '#';  // Ship cell with a reinforcement on it
+const MISS: char = 'o';        // Cell fired at that had no ship on it
+const UNKNOWN_HIT: char = 'x'; // Hit on a ship that hasn't been sunk yet, so its type isn't known
+const SCANNED: char = '+';     // Ship cell found by a radar scan
+
+/* ------ Functions ------ */
+/// Reads a drawn fleet into a board and the ships on it, in the order the rules' fleet lists them.
+/// Fails if the drawing isn't the size of the board, has characters other than water, ship letters and `#`,
+/// or the ships drawn don't make up the rules' fleet (see `fleet_from_ends`).
+pub fn read_fleet(rules: &Rules, text: &str) -> Result<(Board, Vec<Ship>), RulesError> {
+    let rows: Vec<Vec<char>> = text.lines()
+        .map(|line| line.chars().filter(|c| !c.is_whitespace()).collect::<Vec<char>>())
+        .filter(|row| !row.is_empty())
+        .collect();
+    if rows.len() != rules.height {
+        return Err(RulesError::BadFleet(format!("the drawing has {} rows but the board has {}", rows.len(), rules.height)));
+    }
+
+    // Which ship type is on each cell, reinforced cells are worked out from their neighbours below
+    let mut kinds = vec![vec![None; rules.width]; rules.height];
+    let mut ship_cells = vec![vec![false; rules.width]; rules.height];
+    let mut reinforced = Vec::new();
+    for (x, row) in rows.iter().enumerate() {
+        if row.len() != rules.width {
+            return Err(RulesError::BadFleet(format!("row {} is {} cells wide but the board is {}", x + 1, row.len(), rules.width)));
+        }
+        for (y, &c) in row.iter().enumerate() {
+            if c == WATER {
+                continue;
+            }
+            if c == REINFORCED {
+                reinforced.push((x, y));
+            } else {
+                let ship_type = ShipType::from_letter(c)
+                    .ok_or_else(|| RulesError::BadFleet(format!("'{}' at {} isn't water, a ship letter or {}", c, cell_name(x, y), REINFORCED)))?;
+                if !rules.fleet.entries.iter().any(|entry| entry.ship_type == ship_type) {
+                    return Err(RulesError::BadFleet(format!("there's a {:?} at {} but none in the fleet", ship_type, cell_name(x, y))));
+                }
+                kinds[x][y] = Some(ship_type);
+            }
+            ship_cells[x][y] = true;
+        }
+    }
+
+    // A reinforced cell next to only one type of ship is part of it, repeated so a run of them fills in
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(x, y) in &reinforced {
+            if kinds[x][y].is_some() {
+                continue;
+            }
+            let mut around = neighbours(rules, x, y).filter_map(|(nx, ny)| kinds[nx][ny]);
+            if let Some(first) = around.next() {
+                if around.all(|other| other == first) {
+                    kinds[x][y] = Some(first);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    // Every group of touching cells with one letter is one or more ships of that type, end to end or side by side
+    let mut found: Vec<(ShipType, Vec<(usize, usize)>)> = Vec::new();
+    let mut unknown = Vec::new(); // Runs of reinforced cells with no letter next to them
+    let mut seen = vec![vec![false; rules.width]; rules.height];
+    for x in 0..rules.height {
+        for y in 0..rules.width {
+            if !ship_cells[x][y] || seen[x][y] {
+                continue;
+            }
+            let cells = run_at(rules, &ship_cells, &kinds, &mut seen, x, y);
+            let straight = cells.iter().all(|&(cx, _)| cx == x) || cells.iter().all(|&(_, cy)| cy == y);
+            match kinds[x][y] {
+                Some(ship_type) => found.extend(split_group(rules, ship_type, &cells)?.into_iter().map(|ship| (ship_type, ship))),
+                None if straight => unknown.push(cells),
+                None => return Err(RulesError::BadFleet(format!("the reinforced cells at {} aren't a straight line", cell_name(x, y)))),
+            }
+        }
+    }
+
+    // A ship reinforced all over is the only type of its length still missing ships
+    for cells in unknown {
+        let mut missing = rules.fleet.entries.iter()
+            .filter(|entry| entry.length == cells.len())
+            .filter(|entry| found.iter().filter(|(ship_type, ship)| *ship_type == entry.ship_type && ship.len() == entry.length).count() < entry.count)
+            .map(|entry| entry.ship_type);
+        match (missing.next(), missing.next()) {
+            (Some(ship_type), None) => found.push((ship_type, cells)),
+            _ => return Err(RulesError::BadFleet(format!("can't tell which ship the reinforced cells at {} belong to, leave one of its cells unreinforced", cell_name(cells[0].0, cells[0].1)))),
+        }
+    }
+
+    // Hand the ships over in fleet order, the rules check the rest
+    let mut ends: Vec<ShipEnds> = Vec::new();
+    for entry in &rules.fleet.entries {
+        let drawn: Vec<&Vec<(usize, usize)>> = found.iter()
+            .filter(|(ship_type, ship)| *ship_type == entry.ship_type && ship.len() == entry.length)
+            .map(|(_, ship)| ship)
+            .collect();
+        if drawn.len() != entry.count {
+            return Err(RulesError::BadFleet(format!("the fleet has {} but {} drawn", plural(entry.count, &entry.display_name()), plural(drawn.len(), &entry.display_name()))));
+        }
+        ends.extend(drawn.iter().map(|ship| (ship[0], ship[ship.len() - 1])));
+    }
+    if ends.len() != found.len() {
+        return Err(RulesError::BadFleet(format!("{} ships are drawn but the fleet has {}", found.len(), ends.len())));
+    }
+    let ships = fleet_from_ends(rules, &ends)?;
+
+    let mut board = Board::new(rules.width, rules.height);
+    for &(x, y) in ships.iter().flat_map(|ship| ship.positions.iter()) {
+        board.cells[x][y] = Cells::Occupied;
+    }
+    for &(x, y) in &reinforced {
+        board.cells[x][y] = Cells::Reinforced;
+    }
+    Ok((board, ships))
+}
+
+/// Reads a drawn fleet from the file at `path`, see `read_fleet`.
+pub fn load_fleet(rules: &Rules, path: &str) -> Result<(Board, Vec<Ship>), FileError> {
+    let text = std::fs::read_to_string(path).map_err(|e| FileError::Read { path: path.to_string(), reason: e.to_string() })?;
+    read_fleet(rules, &text).map_err(|e| FileError::Format { path: path.to_string(), reason: e.to_string() })
+}
+
+/// Draws `player`'s own board: their ships by letter (lower case where hit), `#` where reinforced and `o` where the opponent missed.
+pub fn write_board(player: &BasePlayer) -> String {
+    draw(&player.board, |x, y, cell| {
+        let letter = player.ships.iter()
+            .find(|ship| ship.positions.contains(&(x, y)))
+            .map(|ship| ship.ship_type.letter());
+        match (cell, letter) {
+            (Cells::Occupied, Some(letter)) => letter,
+            (Cells::Hit, Some(letter)) => letter.to_ascii_lowercase(),
+            (Cells::Occupied, None) => SCANNED,
+            (Cells::Hit, None) => UNKNOWN_HIT,
+            _ => common_cell(cell),
+        }
+    })
+}
+
+/// Draws what `player` knows of the opponent's board: misses, hits (by letter once the ship is sunk) and anything radar found.
+pub fn write_guesses(player: &BasePlayer) -> String {
+    draw(&player.guess_board, |x, y, cell| {
+        let sunk = player.sunk_ships.iter()
+            .find(|ship| ship.positions.contains(&(x, y)))
+            .map(|ship| ship.ship_type.letter().to_ascii_lowercase());
+        match (cell, sunk) {
+            (Cells::Hit, Some(letter)) => letter,
+            (Cells::Hit, None) => UNKNOWN_HIT,
+            (Cells::Occupied, _) => SCANNED,
+            _ => common_cell(cell),
+        }
+    })
+}
+
+/// One line per row of `board`, each cell drawn by `cell_char` from its position and state.
+fn draw(board: &Board, cell_char: impl Fn(usize, usize, Cells) -> char) -> String {
+    let mut text = String::new();
+    for (x, row) in board.cells.iter().enumerate() {
+        text.extend(row.iter().enumerate().map(|(y, &cell)| cell_char(x, y, cell)));
+        text.push('\n');
+    }
+    text
+}
+
+/// How the cells that look the same on either board are drawn
+fn common_cell(cell: Cells) -> char {
+    match cell {
+        Cells::Miss => MISS,
+        Cells::Reinforced => REINFORCED,
+        Cells::Occupied => SCANNED,
+        Cells::Hit => UNKNOWN_HIT,
+        Cells::Empty => WATER,
+    }
+}
+
+/// The cells above, below, left and right of (x, y) that are on the board.
+fn neighbours(rules: &Rules, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+    let (width, height) = (rules.width, rules.height);
+    [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)].into_iter()
+        .filter(move |&(nx, ny)| nx < height && ny < width)
+}
+
+/// Every ship cell joined to (x, y) through cells of the same type, sorted top to bottom then left to right.
+fn run_at(rules: &Rules, ship_cells: &[Vec<bool>], kinds: &[Vec<Option<ShipType>>], seen: &mut [Vec<bool>], x: usize, y: usize) -> Vec<(usize, usize)> {
+    let mut cells = Vec::new();
+    let mut stack = vec![(x, y)];
+    seen[x][y] = true;
+    while let Some((cx, cy)) = stack.pop() {
+        cells.push((cx, cy));
+        for (nx, ny) in neighbours(rules, cx, cy) {
+            if ship_cells[nx][ny] && !seen[nx][ny] && kinds[nx][ny] == kinds[x][y] {
+                seen[nx][ny] = true;
+                stack.push((nx, ny));
+            }
+        }
+    }
+    cells.sort();
+    cells
+}
+
+/// Splits a group of touching `ship_type` cells into straight ships of that type's lengths.
+/// Fails if they can't be split, or can be split more than one way (such as two ships side by side that could
+/// just as well be two ships across them), as then which cells sink together isn't known.
+fn split_group(rules: &Rules, ship_type: ShipType, cells: &[(usize, usize)]) -> Result<Vec<Vec<(usize, usize)>>, RulesError> {
+    let mut lengths: Vec<usize> = rules.fleet.entries.iter()
+        .filter(|entry| entry.ship_type == ship_type)
+        .map(|entry| entry.length)
+        .collect();
+    lengths.dedup();
+
+    let mut splits = Vec::new();
+    find_splits(cells, &lengths, &mut Vec::new(), &mut splits);
+    let (x, y) = cells[0];
+    match splits.len() {
+        0 => Err(RulesError::BadFleet(format!("the {:?} cells at {} don't make whole {:?}s", ship_type, cell_name(x, y), ship_type))),
+        1 => Ok(splits.remove(0)),
+        _ => Err(RulesError::BadFleet(format!("the {:?}s at {} could be split up more than one way, draw them so they don't touch side by side", ship_type, cell_name(x, y)))),
+    }
+}
+
+/// Adds each way of splitting the `cells` not yet in `ships` into straight ships of one of the `lengths` to
+/// `splits`, stopping at two as one more than one is already too many. The first cell left is always the top or
+/// left end of the next ship, so every split is only found once.
+fn find_splits(cells: &[(usize, usize)], lengths: &[usize], ships: &mut Vec<Vec<(usize, usize)>>, splits: &mut Vec<Vec<Vec<(usize, usize)>>>) {
+    let left = |ships: &[Vec<(usize, usize)>], cell: &(usize, usize)| cells.contains(cell) && !ships.iter().any(|ship| ship.contains(cell));
+    let Some(&(x, y)) = cells.iter().find(|cell| left(ships, cell)) else {
+        splits.push(ships.clone());
+        return;
+    };
+
+    for &length in lengths {
+        // A ship of one cell lies both ways, so only try it once
+        for horizontal in [true, false].into_iter().take(if length == 1 { 1 } else { 2 }) {
+            let ship: Vec<(usize, usize)> = (0..length).map(|i| if horizontal { (x, y + i) } else { (x + i, y) }).collect();
+            if splits.len() < 2 && ship.iter().all(|cell| left(ships, cell)) {
+                ships.push(ship);
+                find_splits(cells, lengths, ships, splits);
+                ships.pop();
+            }
+        }
+    }
+}
+
+/// "1 Cruiser" or "2 Cruisers"
+fn plural(count: usize, name: &str) -> String {
+    if count == 1 { format!("1 {}", name) } else { format!("{} {}s", count, name) }
+}
+
+/* ------ Tests ------ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::PlayerId;
+    use crate::game::Game;
+    use crate::rules::{Fleet, FleetEntry, Mode};
+
+    #[test]
+    fn drawn_fleet_reads_in_fleet_order() {
+        // The drawing from the module notes
+        let text = "..........\n.BBBB.....\n........C.\n..N.....#.\n..N.....C.\n..#.......\n..N..SSS..\n..N.......\n......DD..\n..........\n";
+        let rules = Rules::new(Mode::Twist);
+        let (board, ships) = read_fleet(&rules, text).unwrap();
+        let types: Vec<ShipType> = ships.iter().map(|ship| ship.ship_type).collect();
+        assert_eq!(types, [ShipType::Battleship, ShipType::Submarine, ShipType::Cruiser, ShipType::Dreadnaught, ShipType::Destroyer]);
+        assert_eq!(ships[2].positions, [(2, 8), (3, 8), (4, 8)]);
+        assert_eq!(ships[3].positions, [(3, 2), (4, 2), (5, 2), (6, 2), (7, 2)]);
+        assert!(board.cells[3][8] == Cells::Reinforced && board.cells[5][2] == Cells::Reinforced && board.cells[4][8] == Cells::Occupied);
+    }
+
+    #[test]
+    fn written_board_reads_back_the_same() {
+        for seed in 0..5 {
+            let game = Game::with_seed(Rules::new(Mode::Classic).with_fleet(Fleet::eastern_european()), seed).unwrap();
+            let player = &game.player(PlayerId::One).base;
+            let (_, ships) = read_fleet(&game.rules, &write_board(player)).unwrap();
+            // Ships of the same type can come back in another order
+            let positions = |ships: &[Ship]| {
+                let mut positions: Vec<_> = ships.iter().map(|ship| ship.positions.clone()).collect();
+                positions.sort();
+                positions
+            };
+            assert_eq!(positions(&ships), positions(&player.ships), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn ships_that_could_be_split_two_ways_are_refused() {
+        // Two Destroyers in a square could lie across it or down it
+        let rules = Rules::new(Mode::Classic).with_board(5, 5).with_fleet(Fleet::new().with(FleetEntry::new(ShipType::Destroyer, 2, 2)));
+        let read = read_fleet(&rules, "DD...\nDD...\n.....\n.....\n.....\n");
+        assert!(matches!(read, Err(RulesError::BadFleet(_))));
+
+        // With a gap between them there is only one way to read them
+        assert!(read_fleet(&rules, "DD...\n.....\nDD...\n.....\n.....\n").is_ok());
+    }
+
+    #[test]
+    fn reinforced_ship_needs_a_letter_when_its_length_is_shared() {
+        // The Submarine and the Cruiser are both three cells long, so a ship reinforced all over could be either
+        let rules = Rules::new(Mode::Twist);
+        let text = "..........\n.BBBB.....\n.......###\n..........\n..NNNNN...\n..........\n..........\n..........\nDD........\n..........\n";
+        let read = read_fleet(&rules, text);
+        assert!(matches!(read, Err(RulesError::BadFleet(reason)) if reason.contains("can't tell")));
+
+        // Once the Submarine is drawn by letter it can only be the Cruiser
+        let text = "..........\n.BBBB.....\n.......###\n..........\n..NNNNN...\n..........\n.....SSS..\n..........\nDD........\n..........\n";
+        assert!(read_fleet(&rules, text).is_ok());
+    }
+
+    #[test]
+    fn guesses_show_what_is_known() {
+        let mut game = Game::with_seed(Rules::new(Mode::Twist).with_board(5, 5).with_fleet(Fleet::new().with(FleetEntry::new(ShipType::Destroyer, 2, 1))), 1).unwrap();
+        let guess = &mut game.players[0].base.guess_board;
+        guess.cells[0][0] = Cells::Miss;
+        guess.cells[1][1] = Cells::Hit;
+        guess.cells[2][2] = Cells::Occupied;
+        assert_eq!(write_guesses(&game.player(PlayerId::One).base), "o....\n.x...\n..+..\n.....\n.....\n");
+    }
+}
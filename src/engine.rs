@@ -0,0 +1,485 @@
+//! External engines: bots written in any language that play over stdin and stdout.
+//! The game starts the engine as a child process and talks to it one line at a time, a bit like a
+//! chess engine over UCI. `Engine` is an `Opponent`, so an engine can take a seat anywhere the
+//! built in computer players can, in the window and in `battleships-sim`.
+//!
+//! Cells are named by their row's letter then their column from 1 (`A1` is the top left, `C7` is the
+//! third row and seventh column), columns on their own are numbers from 1 and players are 1 or 2.
+//! Lists of cells are joined with commas, e.g. `C7,C8,C9`.
+//!
+//! Starting up, the game sends `battleships 1` and the engine answers `ready`, optionally after a
+//! `name NAME` line. Before the first turn the game describes the game:
+//! - `rules classic|twist WIDTH HEIGHT touching|no-touching`
+//! - `fleet LENGTH COUNT NAME` for each kind of ship
+//! - `player 1|2`, the seat the engine plays
+//! - `ship N CELLS` for each of the engine's own ships, numbered from 1
+//! - `start`
+//!
+//! Whenever something happens the engine is told the next time it is asked for a move:
+//! - `shot PLAYER missile|torpedo CELL miss|hit|sunk|stripped|already`, CELL is where the shot stopped
+//! - `sunk PLAYER CELLS NAME`, PLAYER sank the other player's ship
+//! - `scan PLAYER CELL`, then `reveal CELL water|ship|reinforced|hit|miss` for each cell of its own scans
+//! - `moved N CELLS`, one of its own ships patrolled
+//! - `played PLAYER missile|torpedo|scan|reinforce|patrol`
+//! - `resigned PLAYER` and `gameover PLAYER TURNS`
+//!
+//! Asking for a move, Twist games send `hand CARDS` first (the card names above, space separated),
+//! then `go MILLISECONDS` with the time the engine has to reply. It answers with one of
+//! `fire CELL`, `torpedo COLUMN`, `scan CELL`, `reinforce CELL`, `patrol N up|down|left|right`, `end`
+//! or `resign`. A move that can't be played now gets `illegal REASON` and the engine tries again,
+//! within the same time. The engine is asked again after each move until it ends its turn, except
+//! when ending the turn is the only thing left to do. Lines starting with `info` are ignored.
+//!
+//! A game carried on from a save is described as it was before the first turn, then every move since is
+//! reported the same way, so the engine hears the whole game before it is first asked for a move.
+//!
+//! An engine that runs out of time, stops running or sends too many illegal moves in a turn resigns.
+//! When the engine is no longer needed it is sent `quit`.
+
+/* ------ Import Used Libraries ------ */
+use crate::action::*;
+use crate::ai::Opponent;
+use crate::base::*;
+use crate::error::{EngineError, ReplayError};
+use crate::events::GameEvent;
+use crate::game::Game;
+use crate::replay::Replay;
+use crate::rules::Mode;
+use crate::twist::ActionType;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/*------ Constants ------ */
+pub const DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(5); // Time an engine gets for each move unless told otherwise
+const STARTUP_TIME_LIMIT: Duration = Duration::from_secs(10);    // Time an engine gets to answer `ready`
+const QUIT_TIME_LIMIT: Duration = Duration::from_millis(200);    // Time an engine gets to exit after `quit` before it is killed
+const MAX_ATTEMPTS: usize = 3;                                   // Illegal moves allowed in one turn before the engine resigns
+const PROTOCOL_VERSION: u32 = 1;                                 // Sent in the greeting so engines can tell what to expect
+
+/*------ Enums and Structs ------ */
+/// A running engine process.
+pub struct Engine {
+    name: String,                              // From the engine's `name` line, its command otherwise
+    child: Child,                              // The process itself
+    input: ChildStdin,                         // Lines to the engine
+    output: Receiver<String>,                  // Lines from the engine, read on a background thread
+    time_limit: Duration,                      // Time to reply to each `go`
+    events: Option<Receiver<GameEvent>>,       // The game's events, from joining until dropped
+    id: PlayerId,                              // Seat the engine plays
+    described: bool,                           // Whether the engine has been told the rules and its fleet
+    failure: Option<EngineError>,              // Why the engine stopped playing, it resigns every turn after one
+}
+
+/* ------ Struct Implementations ------ */
+impl Engine {
+    /// Starts `command` (a program and its arguments separated by spaces) and waits for it to say it's ready.
+    /// Each move has to be sent within `time_limit`.
+    pub fn spawn(command: &str, time_limit: Duration) -> Result<Self, EngineError> {
+        let spawn_error = |reason: String| EngineError::Spawn { command: command.to_string(), reason };
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or_else(|| spawn_error("no program given".to_string()))?;
+
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| spawn_error(e.to_string()))?;
+        let input = child.stdin.take().ok_or_else(|| spawn_error("no input pipe".to_string()))?;
+        let stdout = child.stdout.take().ok_or_else(|| spawn_error("no output pipe".to_string()))?;
+
+        // Reading blocks, so it happens on its own thread and the game waits on the channel with a timeout
+        let (tx, output) = channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Engine {
+            name: command.to_string(),
+            child,
+            input,
+            output,
+            time_limit,
+            events: None,
+            id: PlayerId::One,
+            described: false,
+            failure: None,
+        };
+        engine.handshake()?;
+        Ok(engine)
+    }
+
+    /// The engine's name, as it gave it or the command it was started with
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Why the engine stopped playing, if it has
+    pub fn failure(&self) -> Option<&EngineError> {
+        self.failure.as_ref()
+    }
+
+    /// Greets the engine and waits for `ready`.
+    fn handshake(&mut self) -> Result<(), EngineError> {
+        self.send(&format!("battleships {}", PROTOCOL_VERSION))?;
+        let deadline = Instant::now() + STARTUP_TIME_LIMIT;
+        loop {
+            let line = self.read_line(deadline)?;
+            if line == "ready" {
+                return Ok(());
+            }
+            match line.strip_prefix("name ") {
+                Some(name) => self.name = name.trim().to_string(),
+                None => return Err(EngineError::Protocol(line)),
+            }
+        }
+    }
+
+    /// Sends one line to the engine.
+    fn send(&mut self, line: &str) -> Result<(), EngineError> {
+        writeln!(self.input, "{}", line)
+            .and_then(|_| self.input.flush())
+            .map_err(|_| EngineError::Exited)
+    }
+
+    /// Waits until `deadline` for the next line from the engine, skipping blank and `info` lines.
+    fn read_line(&mut self, deadline: Instant) -> Result<String, EngineError> {
+        loop {
+            let line = self.output
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .map_err(|e| match e {
+                    RecvTimeoutError::Timeout => EngineError::Timeout,
+                    RecvTimeoutError::Disconnected => EngineError::Exited,
+                })?;
+            let line = line.trim();
+            if !line.is_empty() && line != "info" && !line.starts_with("info ") {
+                return Ok(line.to_string());
+            }
+        }
+    }
+
+    /// Describes the game to the engine before the first turn.
+    fn describe(&mut self, game: &Game) -> Result<(), EngineError> {
+        let rules = &game.rules;
+        let mode = match rules.mode {
+            Mode::Classic => "classic",
+            Mode::Twist => "twist",
+        };
+        let touching = if rules.no_touching { "no-touching" } else { "touching" };
+        self.send(&format!("rules {} {} {} {}", mode, rules.width, rules.height, touching))?;
+        for entry in &rules.fleet.entries {
+            self.send(&format!("fleet {} {} {}", entry.length, entry.count, entry.display_name()))?;
+        }
+
+        self.described = true;
+        self.send(&format!("player {}", player_number(self.id)))?;
+        for (i, ship) in game.player(self.id).base.ships.iter().enumerate() {
+            self.send(&format!("ship {} {}", i + 1, cell_list(&ship.positions)))?;
+        }
+        self.send("start")
+    }
+
+    /// Brings the engine up to date with a game carried on part way through, playing `id`.
+    /// It is described the game as it was at the start of `replay`, then told every move since, before it
+    /// joins the game itself. Fails if the replay doesn't play out, a protocol failure makes it resign as usual.
+    pub fn catch_up(&mut self, replay: &Replay, id: PlayerId) -> Result<(), ReplayError> {
+        self.id = id;
+        let start = Game::from_state(replay.start.clone()).map_err(ReplayError::Start)?;
+        let mut result = self.describe(&start);
+        replay.play_through(replay.moves.len(), |_, events, game| {
+            let lines: Vec<String> = events.iter().flat_map(|event| self.event_lines(event, Some(game))).collect();
+            for line in lines {
+                if result.is_ok() {
+                    result = self.send(&line);
+                }
+            }
+        })?;
+        if let Err(e) = result {
+            self.failure = Some(e);
+        }
+        Ok(())
+    }
+
+    /// Tells the engine everything that has happened since it was last asked.
+    /// Without the game the details of its own scans and patrols are left out, which only happens once it's over.
+    fn report(&mut self, game: Option<&Game>) -> Result<(), EngineError> {
+        let events: Vec<GameEvent> = match &self.events {
+            Some(events) => events.try_iter().collect(),
+            None => return Ok(()),
+        };
+        for event in events {
+            for line in self.event_lines(&event, game) {
+                self.send(&line)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The protocol lines for one event, none for events the engine doesn't need or shouldn't see.
+    fn event_lines(&self, event: &GameEvent, game: Option<&Game>) -> Vec<String> {
+        let mut lines = Vec::new();
+        match event {
+            GameEvent::ShotResolved { player, weapon, shot } => {
+                let (x, y) = shot.cells.last().copied().unwrap_or((0, 0));
+                let result = match shot.outcome {
+                    ShotOutcome::Miss => "miss",
+                    ShotOutcome::Hit => "hit",
+                    ShotOutcome::Sunk(_) => "sunk",
+                    ShotOutcome::ReinforcementStripped => "stripped",
+                    ShotOutcome::AlreadyHit => "already",
+                };
+                lines.push(format!("shot {} {} {} {}", player_number(*player), card_name(*weapon), cell_name(x, y), result));
+            }
+            GameEvent::ShipSunk { player, name, positions, .. } => {
+                lines.push(format!("sunk {} {} {}", player_number(*player), cell_list(positions), name));
+            }
+            GameEvent::RadarScanned { player, x, y } => {
+                lines.push(format!("scan {} {}", player_number(*player), cell_name(*x, *y)));
+                if let (true, Some(game)) = (*player == self.id, game) {
+                    let guess_board = &game.player(self.id).base.guess_board;
+                    for (dx, dy) in [(0, 0), (0, 1), (0, -1), (1, 0), (-1, 0)] {
+                        let (nx, ny) = (*x as isize + dx, *y as isize + dy);
+                        if guess_board.in_bounds_signed(nx, ny) {
+                            let (nx, ny) = (nx as usize, ny as usize);
+                            lines.push(format!("reveal {} {}", cell_name(nx, ny), state_name(guess_board.cells[nx][ny])));
+                        }
+                    }
+                }
+            }
+            GameEvent::PatrolMoved { player, positions, .. } if *player == self.id => {
+                let ships = game.map(|game| &game.player(self.id).base.ships[..]).unwrap_or(&[]);
+                if let Some(i) = ships.iter().position(|ship| ship.positions == *positions) {
+                    lines.push(format!("moved {} {}", i + 1, cell_list(positions)));
+                }
+            }
+            GameEvent::CardPlayed { player, card } => {
+                lines.push(format!("played {} {}", player_number(*player), card_name(*card)));
+            }
+            GameEvent::Resigned { player } => lines.push(format!("resigned {}", player_number(*player))),
+            GameEvent::GameWon { winner, turns } => lines.push(format!("gameover {} {}", player_number(*winner), turns)),
+            _ => {}
+        }
+        lines
+    }
+
+    /// Asks the engine for its next move and waits for a legal one.
+    fn ask(&mut self, game: &Game, legal: &[Action]) -> Result<Action, EngineError> {
+        self.report(Some(game))?;
+        if game.rules.mode == Mode::Twist {
+            let hand: Vec<&str> = game.player(self.id).hand.iter().map(|&card| card_name(card)).collect();
+            self.send(format!("hand {}", hand.join(" ")).trim_end())?;
+        }
+        self.send(&format!("go {}", self.time_limit.as_millis()))?;
+
+        let deadline = Instant::now() + self.time_limit;
+        for _ in 0..MAX_ATTEMPTS {
+            let line = self.read_line(deadline)?;
+            let reason = match parse_move(&line) {
+                Some(action) if action == Action::Resign || legal.contains(&action) => return Ok(action),
+                Some(_) => "not allowed now",
+                None => "unknown move",
+            };
+            self.send(&format!("illegal {}", reason))?;
+        }
+        Err(EngineError::IllegalMoves)
+    }
+}
+
+/* ------ Trait Implementations ------ */
+impl Opponent for Engine {
+    fn choose_action(&mut self, game: &Game, id: PlayerId) -> Action {
+        self.id = id;
+        if self.failure.is_some() {
+            return Action::Resign;
+        }
+
+        let legal = game.legal_actions(id);
+        if legal == [Action::EndTurn] {
+            return Action::EndTurn;
+        }
+        match self.ask(game, &legal) {
+            Ok(action) => action,
+            Err(e) => {
+                self.failure = Some(e);
+                Action::Resign
+            }
+        }
+    }
+
+    fn join(&mut self, game: &mut Game, id: PlayerId) {
+        self.id = id;
+        self.events = Some(game.events.subscribe());
+        // An engine that caught up on a resumed game has already been described it
+        if self.described {
+            return;
+        }
+        if let Err(e) = self.describe(game) {
+            self.failure = Some(e);
+        }
+    }
+}
+
+/// Passes on the last events and asks the engine to quit, it is killed if it doesn't.
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.report(None);
+        let _ = self.send("quit");
+
+        let deadline = Instant::now() + QUIT_TIME_LIMIT;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/* ------ Functions ------ */
+/// Reads a move such as `fire C7` or `patrol 2 left`, None if it isn't one.
+fn parse_move(line: &str) -> Option<Action> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    // Columns and ship numbers count from 1
+    let number = |word: &str| word.parse::<usize>().ok().filter(|&n| n > 0).map(|n| n - 1);
+
+    let action = match words.as_slice() {
+        ["fire", cell] => parse_cell(cell).map(|(x, y)| Action::Missile { x, y })?,
+        ["torpedo", column] => Action::Torpedo { col: number(column)? },
+        ["scan", cell] => parse_cell(cell).map(|(x, y)| Action::RadarScan { x, y })?,
+        ["reinforce", cell] => parse_cell(cell).map(|(x, y)| Action::Reinforce { x, y })?,
+        ["patrol", ship, dir] => {
+            let dir = match *dir {
+                "up" => Direction::Up,
+                "down" => Direction::Down,
+                "left" => Direction::Left,
+                "right" => Direction::Right,
+                _ => return None,
+            };
+            Action::Patrol { ship: number(ship)?, dir }
+        }
+        ["end"] => Action::EndTurn,
+        ["resign"] => Action::Resign,
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// How a player is written in the protocol
+fn player_number(id: PlayerId) -> u8 {
+    match id {
+        PlayerId::One => 1,
+        PlayerId::Two => 2,
+    }
+}
+
+/// How a card is written in the protocol
+fn card_name(card: ActionType) -> &'static str {
+    match card {
+        ActionType::Missile => "missile",
+        ActionType::Torpedo => "torpedo",
+        ActionType::RadarScan => "scan",
+        ActionType::Reinforce => "reinforce",
+        ActionType::Patrol => "patrol",
+    }
+}
+
+/// How a revealed cell is written in the protocol
+fn state_name(cell: Cells) -> &'static str {
+    match cell {
+        Cells::Empty => "water",
+        Cells::Occupied => "ship",
+        Cells::Reinforced => "reinforced",
+        Cells::Hit => "hit",
+        Cells::Miss => "miss",
+    }
+}
+
+/// Cells joined with commas, e.g. `C7,C8,C9`
+fn cell_list(cells: &[(usize, usize)]) -> String {
+    cells.iter().map(|&(x, y)| cell_name(x, y)).collect::<Vec<_>>().join(",")
+}
+
+/* ------ Tests ------ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_are_read_from_the_protocol() {
+        assert_eq!(parse_move("fire C7"), Some(Action::Missile { x: 2, y: 6 }));
+        assert_eq!(parse_move("torpedo 3"), Some(Action::Torpedo { col: 2 }));
+        assert_eq!(parse_move("scan A1"), Some(Action::RadarScan { x: 0, y: 0 }));
+        assert_eq!(parse_move("reinforce J10"), Some(Action::Reinforce { x: 9, y: 9 }));
+        assert_eq!(parse_move("  patrol 2   left "), Some(Action::Patrol { ship: 1, dir: Direction::Left }));
+        assert_eq!(parse_move("end"), Some(Action::EndTurn));
+        assert_eq!(parse_move("resign"), Some(Action::Resign));
+    }
+
+    #[test]
+    fn anything_else_is_not_a_move() {
+        for line in ["", "fire", "fire C", "fire 7C", "torpedo 0", "torpedo C", "patrol 0 up", "patrol 1 sideways", "end now", "pass"] {
+            assert_eq!(parse_move(line), None, "{:?}", line);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn caught_up_engine_hears_the_whole_game_without_being_asked_to_move() {
+        use crate::ai::Difficulty;
+        use crate::game::Phase;
+        use crate::replay::ReplayRecorder;
+        use crate::rules::Rules;
+        use crate::save::Seat;
+        use std::os::unix::fs::PermissionsExt;
+
+        // An engine that answers the greeting and writes down everything it is sent
+        let dir = std::env::temp_dir().join(format!("battleships-engine-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (script, log) = (dir.join("listen.sh"), dir.join("heard.txt"));
+        std::fs::write(&script, "#!/bin/sh\necho ready\ncat > \"$1\"\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut game = Game::with_seed(Rules::new(Mode::Classic), 3).unwrap();
+        let mut players = [Difficulty::Normal.opponent(3), Difficulty::Normal.opponent(4)];
+        let mut recorder = ReplayRecorder::new(&mut game, Seat::Engine(script.display().to_string()));
+        game.start();
+        for _ in 0..20 {
+            match game.phase {
+                Phase::Turn(id) => {
+                    let action = players[if id == PlayerId::One { 0 } else { 1 }].choose_action(&game, id);
+                    game.apply(action).unwrap();
+                    recorder.record(&game, id, action);
+                }
+                _ => game.begin_turn(),
+            }
+        }
+
+        let command = format!("{} {}", script.display(), log.display());
+        let mut engine = Engine::spawn(&command, Duration::from_secs(1)).unwrap();
+        engine.catch_up(recorder.replay(), PlayerId::Two).unwrap();
+        assert!(engine.failure().is_none());
+        drop(engine);
+
+        let heard = std::fs::read_to_string(&log).unwrap();
+        let lines: Vec<&str> = heard.lines().collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(lines.first(), Some(&"battleships 1"));
+        assert!(lines.contains(&"player 2"));
+        let start = lines.iter().position(|&line| line == "start").unwrap();
+        let shots = lines.iter().filter(|line| line.starts_with("shot ")).count();
+        let fired = recorder.replay().moves.iter().filter(|step| matches!(step.action, Action::Missile { .. })).count();
+        assert_eq!(shots, fired);
+        assert!(lines[..start].iter().all(|line| !line.starts_with("shot ")));
+        assert!(lines.iter().all(|line| !line.starts_with("go ")));
+        assert_eq!(lines.last(), Some(&"quit"));
+    }
+}
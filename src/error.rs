@@ -0,0 +1,191 @@
+//! Errors returned when a player action can't be carried out.
+//! Every action in `base.rs` and `twist.rs` returns `Result<_, ActionError>` so frontends and bots
+//! can tell exactly why something failed instead of guessing from a bool.
+//! `RulesError` covers settings that can't make a playable game, it is returned before any turn is taken.
+//! `EngineError` says why an external engine couldn't be started or stopped answering.
+//! `FileError` says why something saved to disk couldn't be read or written.
+//! `ReplayError` says where a recorded game stopped matching the rules it is replayed against.
+//! `NotationError` says which line of a written down game couldn't be read.
+
+/* ------ Import Used Libraries ------ */
+use crate::action::Action;
+use crate::rules::{MAX_BOARD_SIZE, MIN_BOARD_SIZE};
+use crate::twist::ActionType;
+use std::fmt;
+
+/*------ Enums and Structs ------ */
+/// Reasons a player action can fail.
+#[derive(Clone, Copy, PartialEq, Debug)] // Debug - Allows the error to be printed with {:?}
+pub enum ActionError {
+    OutOfBounds { x: usize, y: usize },       // Target is not on the board
+    AlreadyTargeted { x: usize, y: usize },   // Cell has already been hit or missed
+    CardNotInHand(ActionType),                // Player tried to use a card they don't hold
+    ShipDamaged,                              // Hit ships can't patrol and hit cells can't be reinforced
+    NotOwnShip { x: usize, y: usize },        // No ship of the player's at that cell
+    AlreadyReinforced { x: usize, y: usize }, // Cell already has its extra life
+    PatrolBlocked,                            // Patrol move would leave the board or hit another ship
+    NotPatrolling,                            // Patrol move without a ship selected
+    NoSuchShip(usize),                        // No ship with that index in the player's fleet
+    DeckExhausted,                            // No cards left to draw
+    NotYourTurn,                              // Acted while no turn is in progress (handoff or game over)
+    AlreadyActed,                             // Only one action is allowed per turn
+    MustActFirst,                             // Tried to end the turn without acting
+    TwistOnly,                                // Card actions aren't part of Classic mode
+}
+
+/// Reasons a set of rules can't be used to start a game.
+#[derive(Clone, PartialEq, Debug)] // Debug - Allows the error to be printed with {:?}
+pub enum RulesError {
+    BoardSize { width: usize, height: usize },        // Board is smaller or larger than the supported sizes
+    EmptyFleet,                                       // No ships to place, so nobody could ever win
+    ShipDoesNotFit { name: String, length: usize },   // Ship is longer than the board, or has no cells
+    FleetTooLarge { cells: usize, board_cells: usize }, // Ships take up more cells than the board has
+    NoLayout { width: usize, height: usize, no_touching: bool }, // Every way of placing the fleet was tried and none fit
+    GaveUp { width: usize, height: usize, no_touching: bool },   // The board was so crowded the search ran out of steps before finding a layout or ruling them all out
+    Inconsistent(String),                             // A saved game doesn't fit its own rules
+    BadFleet(String),                                 // A fleet laid out by hand doesn't fit the rules
+}
+
+/// Reasons an external engine couldn't be used.
+#[derive(Clone, PartialEq, Debug)] // Debug - Allows the error to be printed with {:?}
+pub enum EngineError {
+    Spawn { command: String, reason: String }, // The program couldn't be started
+    Timeout,                                   // No reply within the time limit
+    Exited,                                    // The program closed its output or couldn't be written to
+    Protocol(String),                          // A reply that isn't part of the protocol where one was needed
+    IllegalMoves,                              // Too many illegal replies in one turn
+}
+
+/// Reasons a file couldn't be loaded or saved.
+#[derive(Clone, PartialEq, Debug)] // Debug - Allows the error to be printed with {:?}
+pub enum FileError {
+    Read { path: String, reason: String },   // The file couldn't be opened or read
+    Write { path: String, reason: String },  // The file couldn't be created or written
+    Format { path: String, reason: String }, // The file was read but isn't what was expected
+}
+
+/// Reasons a recorded game can't be played back.
+#[derive(Clone, PartialEq, Debug)] // Debug - Allows the error to be printed with {:?}
+pub enum ReplayError {
+    Start(RulesError),                                            // The starting position isn't a playable game
+    Refused { index: usize, action: Action, error: ActionError }, // The move numbered `index` (from 1) isn't allowed at that point
+    Diverged { index: usize },                                    // The move numbered `index` drew different cards than when it was recorded
+}
+
+/// Reasons a game can't be written down in notation or read back from it.
+#[derive(Clone, PartialEq, Debug)] // Debug - Allows the error to be printed with {:?}
+pub enum NotationError {
+    Header(String),                       // The header is missing a line or has a value that can't be read
+    Start(RulesError),                    // The header doesn't describe a playable game
+    Move { line: usize, reason: String }, // A move can't be read or isn't allowed, `line` counts from 1
+    Replay(ReplayError),                  // The recording being written down doesn't play back
+    Unwritable(String),                   // The recording can't be written down
+}
+
+/* ------ Trait Implementations ------ */
+/// Player facing messages for each error
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionError::OutOfBounds { x, y } => write!(f, "({}, {}) is not on the board.", x, y),
+            ActionError::AlreadyTargeted { x, y } => write!(f, "({}, {}) has already been targeted.", x, y),
+            ActionError::CardNotInHand(card) => write!(f, "You can't use {:?}, it isn't in your hand.", card),
+            ActionError::ShipDamaged => write!(f, "That ship has been hit."),
+            ActionError::NotOwnShip { x, y } => write!(f, "You don't have a ship at ({}, {}).", x, y),
+            ActionError::AlreadyReinforced { x, y } => write!(f, "Cell at ({}, {}) is already reinforced.", x, y),
+            ActionError::PatrolBlocked => write!(f, "The ship can't move there."),
+            ActionError::NotPatrolling => write!(f, "No ship is selected for patrol."),
+            ActionError::NoSuchShip(ship) => write!(f, "You don't have a ship number {}.", ship),
+            ActionError::DeckExhausted => write!(f, "The deck is empty."),
+            ActionError::NotYourTurn => write!(f, "It isn't your turn."),
+            ActionError::AlreadyActed => write!(f, "You have already acted this turn."),
+            ActionError::MustActFirst => write!(f, "You need to act before ending your turn."),
+            ActionError::TwistOnly => write!(f, "That action is only in Twist mode."),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+/// Messages shown when a game can't be started
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RulesError::BoardSize { width, height } => write!(
+                f, "A {}x{} board isn't supported, each side must be between {} and {}.",
+                width, height, MIN_BOARD_SIZE, MAX_BOARD_SIZE
+            ),
+            RulesError::EmptyFleet => write!(f, "The fleet has no ships."),
+            RulesError::ShipDoesNotFit { name, length } => write!(f, "A {} of length {} doesn't fit on the board.", name, length),
+            RulesError::FleetTooLarge { cells, board_cells } => write!(f, "The fleet needs {} cells but the board only has {}.", cells, board_cells),
+            RulesError::NoLayout { width, height, no_touching } => write!(
+                f, "The fleet can't be placed on a {}x{} board{}.",
+                width, height, if *no_touching { " without ships touching" } else { "" }
+            ),
+            RulesError::GaveUp { width, height, no_touching } => write!(
+                f, "No layout of the fleet turned up in time on a {}x{} board{}, it may not fit at all.",
+                width, height, if *no_touching { " without ships touching" } else { "" }
+            ),
+            RulesError::Inconsistent(reason) => write!(f, "The saved game doesn't fit its rules: {}.", reason),
+            RulesError::BadFleet(reason) => write!(f, "The fleet doesn't fit the rules: {}.", reason),
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+/// Messages shown when an engine is dropped from a game
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EngineError::Spawn { command, reason } => write!(f, "Couldn't start engine '{}': {}", command, reason),
+            EngineError::Timeout => write!(f, "The engine didn't reply in time."),
+            EngineError::Exited => write!(f, "The engine stopped running."),
+            EngineError::Protocol(line) => write!(f, "The engine sent '{}', which isn't part of the protocol.", line),
+            EngineError::IllegalMoves => write!(f, "The engine kept choosing moves that aren't allowed."),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+/// Messages shown when a file can't be used
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileError::Read { path, reason } => write!(f, "Couldn't read '{}': {}", path, reason),
+            FileError::Write { path, reason } => write!(f, "Couldn't write '{}': {}", path, reason),
+            FileError::Format { path, reason } => write!(f, "'{}' isn't in the expected format: {}", path, reason),
+        }
+    }
+}
+
+impl std::error::Error for FileError {}
+
+/// Messages shown when a replay can't be played back
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Start(e) => write!(f, "The replay's starting position can't be played: {}", e),
+            ReplayError::Refused { index, action, error } => write!(f, "Move {} ({:?}) isn't allowed: {}", index, action, error),
+            ReplayError::Diverged { index } => write!(f, "Move {} drew different cards than when it was recorded, the rules must have changed.", index),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Messages shown when notation can't be written or read
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::Header(reason) => write!(f, "The header {}.", reason),
+            NotationError::Start(e) => write!(f, "The header doesn't describe a playable game: {}", e),
+            NotationError::Move { line, reason } => write!(f, "Line {}: {}.", line, reason),
+            NotationError::Replay(e) => write!(f, "{}", e),
+            NotationError::Unwritable(reason) => write!(f, "The game can't be written down, {}.", reason),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
@@ -0,0 +1,87 @@
+//! Typed feedback from the game rules.
+//! Actions emit a `GameEvent` onto an `EventBus` instead of printing, and anything that wants to
+//! know what happened (the on-screen log, sound effects, a log file, a replay recorder, a network peer)
+//! subscribes and reads the events from its own channel.
+
+/* ------ Import Used Libraries ------ */
+use crate::base::*;
+use crate::twist::ActionType;
+use std::fmt;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/*------ Enums and Structs ------ */
+/// Something that happened in the game.
+#[derive(Clone, PartialEq, Debug)] // Clone - Each subscriber gets its own copy. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}
+pub enum GameEvent {
+    ShotResolved { player: PlayerId, weapon: ActionType, shot: Shot },                  // A missile or torpedo landed
+    ShipSunk { player: PlayerId, ship_type: ShipType, name: String, positions: Vec<(usize, usize)> }, // `player` sank one of the opponent's ships
+    RadarScanned { player: PlayerId, x: usize, y: usize },                             // Radar scan centred on (x, y)
+    CellReinforced { player: PlayerId, x: usize, y: usize },                           // One of the player's ship cells got an extra life
+    CardDrawn { player: PlayerId, card: ActionType },                                  // Card taken from the deck
+    CardPlayed { player: PlayerId, card: ActionType },                                 // Card used from the hand
+    PatrolStarted { player: PlayerId, ship_type: ShipType, name: String },             // Ship selected for a patrol move
+    PatrolMoved { player: PlayerId, ship_type: ShipType, name: String, positions: Vec<(usize, usize)> }, // Ship moved to its new positions
+    PatrolTimedOut { player: PlayerId },                                               // No move was made in time, the card went back to the hand
+    TurnEnded { player: PlayerId },                                                    // Player ended their turn
+    Resigned { player: PlayerId },                                                     // Player gave the game up, `GameWon` follows
+    GameWon { winner: PlayerId, turns: u32 },                                          // Every opposing ship has been sunk, or the opponent resigned
+}
+
+/// Hands every emitted event to each subscriber.
+#[derive(Default)] // Default - A bus with no subscribers
+pub struct EventBus {
+    subscribers: Vec<Sender<GameEvent>>, // One channel per subscriber
+}
+
+/* ------ Struct Implementations ------ */
+impl EventBus {
+    /// Creates a bus with no subscribers, events emitted on it are simply dropped.
+    pub fn new() -> Self {
+        EventBus {
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Adds a subscriber, every event emitted from now on can be read from the returned channel.
+    pub fn subscribe(&mut self) -> Receiver<GameEvent> {
+        let (tx, rx) = channel();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    /// Sends an event to every subscriber.
+    /// Subscribers that have dropped their receiver are removed.
+    pub fn emit(&mut self, event: GameEvent) {
+        self.subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+}
+
+/* ------ Trait Implementations ------ */
+/// One line descriptions used for the on-screen log
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameEvent::ShotResolved { player, weapon, shot } => {
+                let (x, y) = shot.cells.last().copied().unwrap_or((0, 0));
+                let result = match shot.outcome {
+                    ShotOutcome::Miss => "Miss!",
+                    ShotOutcome::Hit | ShotOutcome::Sunk(_) => "Hit!",
+                    ShotOutcome::ReinforcementStripped => "Hit a reinforced cell! Protection removed.",
+                    ShotOutcome::AlreadyHit => "Stopped! Already hit here.",
+                };
+                write!(f, "{} {:?} at ({}, {}): {}", player, weapon, x, y, result)
+            }
+            GameEvent::ShipSunk { player, name, .. } => write!(f, "{} destroyed a {}!", player, name),
+            GameEvent::RadarScanned { player, x, y } => write!(f, "{} radar scan at ({}, {}) complete!", player, x, y),
+            GameEvent::CellReinforced { player, x, y } => write!(f, "{} reinforced ({}, {})", player, x, y),
+            GameEvent::CardDrawn { player, card } => write!(f, "{} drew {:?}", player, card),
+            GameEvent::CardPlayed { player, card } => write!(f, "{} played {:?}", player, card),
+            GameEvent::PatrolStarted { player, name, .. } => write!(f, "{} is moving their {}", player, name),
+            GameEvent::PatrolMoved { player, name, .. } => write!(f, "{} moved their {}", player, name),
+            GameEvent::PatrolTimedOut { player } => write!(f, "{} patrol move timed out - card returned to hand", player),
+            GameEvent::TurnEnded { player } => write!(f, "{} ended their turn", player),
+            GameEvent::Resigned { player } => write!(f, "{} resigned", player),
+            GameEvent::GameWon { winner, turns } => write!(f, "{} won after {} turns!", winner, turns),
+        }
+    }
+}
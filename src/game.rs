@@ -0,0 +1,656 @@
+//! The turn flow shared by every frontend.
+//! `Game` owns both players and moves through explicit phases (setup, turns, the handoff screen
+//! between turns and game over), so the rules about who may act, when a turn can end and who has
+//! won live here instead of in the window loop.
+
+/* ------ Import Used Libraries ------ */
+use crate::action::{Action, Direction};
+use crate::base::*;
+use crate::error::{ActionError, RulesError};
+use crate::events::{EventBus, GameEvent};
+use crate::placement::Strategy;
+use crate::rules::*;
+use crate::twist::*;
+// Random library
+use ::rand::prelude::*;
+use ::rand::rngs::StdRng;
+// Saving and loading
+use serde::{Deserialize, Serialize};
+
+/*------ Enums and Structs ------ */
+/// Where the game is up to.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)] // Copy - Enables bitwise copying of the type. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}. Serialize/Deserialize - Allows saving and loading
+pub enum Phase {
+    Setup,                                    // Fleets placed, waiting for `start()`
+    Turn(PlayerId),                           // That player may act then end their turn
+    Handoff,                                  // Between turns, so the device can be passed to the next player
+    GameOver { winner: PlayerId, turns: u32 }, // Every ship of the loser has been sunk, or they resigned
+}
+
+/// A full two player game.
+pub struct Game {
+    pub rules: Rules,              // Mode and board size, fixed for the whole game
+    pub phase: Phase,              // Current phase
+    pub players: [TwistPlayer; 2], // Player one then player two, Classic players just have no cards
+    pub turn: u32,                 // Round number, goes up each time player one starts a turn
+    pub acted: bool,               // Whether the current player has acted this turn
+    pub events: EventBus,          // Feedback for the ui and anything else listening
+    pub seed: u64,                 // Seed the game's randomness started from, the same seed deals the same game
+    pub layouts: [Strategy; 2],    // How player one's then player two's fleet was laid out
+    next: PlayerId,                // Who plays after the handoff
+    rng: CountedRng,               // The only source of randomness in the rules (fleet placement and deck shuffles)
+}
+
+/// The game's random stream, keeping count of how far through it the game is so a saved game can pick it up
+/// at the same point. The standard RNG always uses up whole 32-bit words in order, so the count is all it takes.
+#[derive(Clone)] // Clone - Creates a deep copy of the value, so a search can play on a copy of the game
+struct CountedRng {
+    seed: u64,   // Seed the stream started from
+    words: u64,  // 32-bit words used so far
+    rng: StdRng, // The stream itself
+}
+
+/// Everything in a `Game` needed to carry it on later, see `save.rs`.
+/// Subscribers to the events aren't part of it, the random stream is saved as how far through it the game was.
+#[derive(Clone, Serialize, Deserialize)] // Clone - Creates a deep copy of the value. Serialize/Deserialize - Allows saving and loading
+pub struct GameState {
+    pub rules: Rules,              // Mode, board and fleet
+    pub phase: Phase,              // Where the game was up to
+    pub players: [TwistPlayer; 2], // Boards, ships, decks, hands and patrol state of both players
+    pub turn: u32,                 // Round number
+    pub acted: bool,               // Whether the current player had acted this turn
+    pub seed: u64,                 // Seed the game was first dealt from
+    pub layouts: [Strategy; 2],    // How each fleet was laid out
+    pub next: PlayerId,            // Who plays after the handoff
+    pub rng_seed: u64,             // Seed the game's random stream started from
+    pub rng_words: u64,            // How much of the random stream had been used
+}
+
+/* ------ Struct Implementations ------ */
+impl Game {
+    /// Creates a game from a random seed with both fleets placed, waiting in Setup.
+    /// Fails if the rules don't describe a playable game or a fleet can't be placed (or no layout turned up in time).
+    pub fn new(rules: Rules) -> Result<Self, RulesError> {
+        Game::with_seed(rules, ::rand::random())
+    }
+
+    /// Creates a game from `seed`, the same rules and seed always give the same fleets and decks
+    /// (on the same build, the standard RNG's output can change between versions of the rand crate).
+    pub fn with_seed(rules: Rules, seed: u64) -> Result<Self, RulesError> {
+        Game::with_layouts(rules, seed, [Strategy::Random; 2])
+    }
+
+    /// Creates a game from `seed` with each fleet laid out in the style given for that player,
+    /// the same rules, seed and layouts always give the same game.
+    pub fn with_layouts(rules: Rules, seed: u64, layouts: [Strategy; 2]) -> Result<Self, RulesError> {
+        rules.validate()?;
+        let mut rng = CountedRng::new(seed, 0);
+
+        let mut make_player = |id: PlayerId, layout| -> Result<TwistPlayer, RulesError> {
+            let base = BasePlayer::new(id, &rules, layout, &mut rng)?;
+            Ok(match rules.mode {
+                Mode::Classic => TwistPlayer::classic(base),
+                Mode::Twist => TwistPlayer::new(base, &mut rng),
+            })
+        };
+        let players = [make_player(PlayerId::One, layouts[0])?, make_player(PlayerId::Two, layouts[1])?];
+
+        Ok(Game {
+            rules,
+            phase: Phase::Setup,
+            players,
+            turn: 1,
+            acted: false,
+            events: EventBus::new(),
+            seed,
+            layouts,
+            next: PlayerId::One,
+            rng,
+        })
+    }
+
+    /// A copy of the game with nobody listening to its events and its own random stream from `seed`,
+    /// for trying moves out without touching the real game.
+    pub fn sandbox(&self, seed: u64) -> Game {
+        Game {
+            rules: self.rules.clone(),
+            phase: self.phase,
+            players: self.players.clone(),
+            turn: self.turn,
+            acted: self.acted,
+            events: EventBus::new(),
+            seed: self.seed,
+            layouts: self.layouts,
+            next: self.next,
+            rng: CountedRng::new(seed, 0),
+        }
+    }
+
+    /// Swaps `id`'s fleet for `ships` before the game starts, for fleets laid out some other way than a `Strategy`.
+    /// The ships are expected to fit the board and the rules. Does nothing once the game has started.
+    pub fn set_fleet(&mut self, id: PlayerId, ships: Vec<Ship>) {
+        if self.phase == Phase::Setup {
+            pair_mut(&mut self.players, id).0.base = BasePlayer::with_ships(id, &self.rules, ships);
+        }
+    }
+
+    /// Like `set_fleet`, but with `board` as `id`'s own board so cells can start reinforced, e.g. a fleet read
+    /// from a diagram. The board is expected to be the rules' size with the ships on it. Does nothing once the game has started.
+    pub fn set_board(&mut self, id: PlayerId, board: Board, ships: Vec<Ship>) {
+        if self.phase == Phase::Setup {
+            let base = &mut pair_mut(&mut self.players, id).0.base;
+            *base = BasePlayer::with_ships(id, &self.rules, ships);
+            base.board = board;
+        }
+    }
+
+    /// Everything needed to carry the game on later, a game loaded from it deals the same cards from here on as this one.
+    pub fn state(&self) -> GameState {
+        GameState {
+            rules: self.rules.clone(),
+            phase: self.phase,
+            players: self.players.clone(),
+            turn: self.turn,
+            acted: self.acted,
+            seed: self.seed,
+            layouts: self.layouts,
+            next: self.next,
+            rng_seed: self.rng.seed,
+            rng_words: self.rng.words,
+        }
+    }
+
+    /// Carries on a game from a saved state. Fails if the rules aren't playable or the boards, ships and
+    /// scans don't fit them, which only happens when a save file has been edited or is from another version.
+    pub fn from_state(state: GameState) -> Result<Self, RulesError> {
+        state.rules.validate()?;
+        let (width, height) = (state.rules.width, state.rules.height);
+        let on_board = |&(x, y): &(usize, usize)| x < height && y < width;
+
+        for (player, id) in state.players.iter().zip([PlayerId::One, PlayerId::Two]) {
+            let base = &player.base;
+            if base.id != id {
+                return Err(RulesError::Inconsistent(format!("the players are saved as {} and {}", state.players[0].base.id, state.players[1].base.id)));
+            }
+            for board in [&base.board, &base.guess_board] {
+                if board.width != width || board.height != height || board.cells.len() != height || board.cells.iter().any(|row| row.len() != width) {
+                    return Err(RulesError::Inconsistent(format!("{}'s boards aren't {}x{}", base.id, width, height)));
+                }
+            }
+            let ships = base.ships.iter().chain(&base.sunk_ships);
+            if ships.flat_map(|ship| &ship.positions).chain(&player.scanned_cells).any(|cell| !on_board(cell)) {
+                return Err(RulesError::Inconsistent(format!("{} has a ship or scan off the board", base.id)));
+            }
+            if player.patrol_ship.is_some_and(|ship| ship >= base.ships.len()) {
+                return Err(RulesError::Inconsistent(format!("{} is patrolling a ship they don't have", base.id)));
+            }
+        }
+
+        Ok(Game {
+            rules: state.rules,
+            phase: state.phase,
+            players: state.players,
+            turn: state.turn,
+            acted: state.acted,
+            events: EventBus::new(),
+            seed: state.seed,
+            layouts: state.layouts,
+            next: state.next,
+            rng: CountedRng::new(state.rng_seed, state.rng_words),
+        })
+    }
+
+    /// Leaves Setup and starts player one's first turn.
+    pub fn start(&mut self) {
+        if self.phase == Phase::Setup {
+            self.phase = Phase::Turn(PlayerId::One);
+        }
+    }
+
+    /// Returns a player by id
+    pub fn player(&self, id: PlayerId) -> &TwistPlayer {
+        match id {
+            PlayerId::One => &self.players[0],
+            PlayerId::Two => &self.players[1],
+        }
+    }
+
+    /// Returns whose turn it is, if it is anyone's.
+    pub fn current(&self) -> Option<PlayerId> {
+        match self.phase {
+            Phase::Turn(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Carries out `action` for the current player.
+    /// This is the one entry point for input, AI, network play and replays, the methods below are what it calls.
+    pub fn apply(&mut self, action: Action) -> Result<(), ActionError> {
+        match action {
+            Action::Missile { x, y } => self.fire_missile(x, y).map(|_| ()),
+            Action::Torpedo { col } => self.fire_torpedo(col).map(|_| ()),
+            Action::RadarScan { x, y } => self.radar_scan(x, y),
+            Action::Reinforce { x, y } => self.reinforce(x, y),
+            Action::Patrol { ship, dir } => self.patrol(ship, dir),
+            Action::EndTurn => self.end_turn(),
+            Action::Resign => self.resign(),
+        }
+    }
+
+    /// Returns every action `id` could apply right now.
+    /// Empty unless it is their turn. Ending the turn is offered once they have acted,
+    /// or as a pass if they hold no card that can be played. Resigning is always allowed on your turn but isn't listed.
+    pub fn legal_actions(&self, id: PlayerId) -> Vec<Action> {
+        if self.current() != Some(id) {
+            return Vec::new();
+        }
+
+        let mut actions = self.moves(id);
+        if actions.is_empty() {
+            actions.push(Action::EndTurn);
+        }
+        actions
+    }
+
+    /// Returns true if the current player can end their turn.
+    pub fn can_end_turn(&self) -> bool {
+        match self.current() {
+            Some(id) => self.acted || self.moves(id).is_empty(),
+            None => false,
+        }
+    }
+
+    /// Fires a missile at the opponent's (x, y).
+    pub fn fire_missile(&mut self, x: usize, y: usize) -> Result<Shot, ActionError> {
+        let shot = self.act(ActionType::Missile, |player, opponent, events| {
+            player.base.fire_missile(&mut opponent.base, x, y, events)
+        })?;
+        self.check_win();
+        Ok(shot)
+    }
+
+    /// Fires a torpedo up the opponent's column (Twist only).
+    pub fn fire_torpedo(&mut self, column: usize) -> Result<Shot, ActionError> {
+        let shot = self.act(ActionType::Torpedo, |player, opponent, events| {
+            player.fire_torpedo(opponent, column, events)
+        })?;
+        self.check_win();
+        Ok(shot)
+    }
+
+    /// Radar scans around the opponent's (x, y) (Twist only).
+    pub fn radar_scan(&mut self, x: usize, y: usize) -> Result<(), ActionError> {
+        self.act(ActionType::RadarScan, |player, opponent, events| {
+            player.radar_scan(opponent, x, y, events)
+        })
+    }
+
+    /// Reinforces one of the current player's own cells (Twist only).
+    pub fn reinforce(&mut self, x: usize, y: usize) -> Result<(), ActionError> {
+        self.act(ActionType::Reinforce, |player, _, events| player.reinforce(x, y, events))
+    }
+
+    /// Selects the ship at the current player's (x, y) for a patrol move (Twist only).
+    /// The card is spent straight away and comes back if the move times out, this doesn't count as acting.
+    pub fn start_patrol(&mut self, x: usize, y: usize) -> Result<(), ActionError> {
+        let id = self.check_can_act(ActionType::Patrol)?;
+        let (player, _) = pair_mut(&mut self.players, id);
+        player.has_card(ActionType::Patrol)?;
+        if player.patrol_mode {
+            return Err(ActionError::AlreadyActed);
+        }
+        player.start_patrol(x, y, &mut self.events)?;
+        pair_mut(&mut self.players, id).0.use_card(ActionType::Patrol, &mut self.events)
+    }
+
+    /// Moves ship number `ship` one cell in `dir`, this is the patrol action (Twist only).
+    /// Finishes the patrol if that ship was selected with `start_patrol`, otherwise selects and moves it in one go.
+    pub fn patrol(&mut self, ship: usize, dir: Direction) -> Result<(), ActionError> {
+        let id = self.check_can_act(ActionType::Patrol)?;
+        let (dir_x, dir_y) = dir.delta();
+        let (player, _) = pair_mut(&mut self.players, id);
+
+        if !(player.patrol_mode && player.patrol_ship == Some(ship)) {
+            // The card of a different selected ship comes back, so it only needs to be in hand otherwise
+            if !player.patrol_mode {
+                player.has_card(ActionType::Patrol)?;
+            }
+            player.check_can_patrol(ship)?;
+            player.patrol_destination(ship, dir_x, dir_y)?;
+
+            if player.patrol_mode {
+                player.cancel_patrol(true);
+            }
+            player.use_card(ActionType::Patrol, &mut self.events)?;
+            player.patrol_mode = true;
+            player.patrol_ship = Some(ship);
+        }
+
+        player.try_patrol_move(dir_x, dir_y, &mut self.events)?;
+        self.acted = true;
+        Ok(())
+    }
+
+    /// Advances per-frame timers, currently just the patrol time limit.
+    pub fn tick(&mut self) {
+        if let Some(id) = self.current() {
+            let (player, _) = pair_mut(&mut self.players, id);
+            player.update_patrol(&mut self.events);
+        }
+    }
+
+    /// Ends the current player's turn once they have acted, or passes if none of their cards can be played.
+    /// In Twist mode they draw back up to a full hand, then the game waits on the handoff screen.
+    /// A player who passes swaps their whole hand for new cards so they aren't stuck with it.
+    pub fn end_turn(&mut self) -> Result<(), ActionError> {
+        let id = self.current().ok_or(ActionError::NotYourTurn)?;
+        let passing = !self.acted;
+        if passing && !self.moves(id).is_empty() {
+            return Err(ActionError::MustActFirst);
+        }
+
+        if self.rules.mode == Mode::Twist {
+            let (player, _) = pair_mut(&mut self.players, id);
+            // A ship still waiting for a patrol move gives its card back
+            if player.patrol_mode {
+                player.cancel_patrol(true);
+            }
+            if passing {
+                player.hand.clear();
+            }
+            player.draw_hand(&mut self.events, &mut self.rng);
+        }
+
+        self.events.emit(GameEvent::TurnEnded { player: id });
+        self.acted = false;
+        self.next = id.other();
+        self.phase = Phase::Handoff;
+        Ok(())
+    }
+
+    /// Gives the game to the current player's opponent.
+    pub fn resign(&mut self) -> Result<(), ActionError> {
+        let id = self.current().ok_or(ActionError::NotYourTurn)?;
+        let winner = id.other();
+        self.events.emit(GameEvent::Resigned { player: id });
+        self.phase = Phase::GameOver { winner, turns: self.turn };
+        self.events.emit(GameEvent::GameWon { winner, turns: self.turn });
+        Ok(())
+    }
+
+    /// Leaves the handoff screen and starts the next player's turn.
+    pub fn begin_turn(&mut self) {
+        if self.phase == Phase::Handoff {
+            if self.next == PlayerId::One {
+                self.turn += 1;
+            }
+            self.phase = Phase::Turn(self.next);
+        }
+    }
+
+    /// Every action except ending the turn that `id` could apply right now.
+    fn moves(&self, id: PlayerId) -> Vec<Action> {
+        let mut actions = Vec::new();
+        if self.current() != Some(id) || self.acted {
+            return actions;
+        }
+
+        let player = self.player(id);
+        let board = &player.base.board;
+        let guess_board = &player.base.guess_board;
+        let holds = |card| match self.rules.mode {
+            Mode::Classic => card == ActionType::Missile,
+            Mode::Twist => player.hand.contains(&card),
+        };
+
+        if holds(ActionType::Missile) {
+            for x in 0..guess_board.height {
+                for y in 0..guess_board.width {
+                    if !matches!(guess_board.cells[x][y], Cells::Hit | Cells::Miss) {
+                        actions.push(Action::Missile { x, y });
+                    }
+                }
+            }
+        }
+        if holds(ActionType::Torpedo) {
+            for col in 0..guess_board.width {
+                actions.push(Action::Torpedo { col });
+            }
+        }
+        if holds(ActionType::RadarScan) {
+            for x in 0..guess_board.height {
+                for y in 0..guess_board.width {
+                    actions.push(Action::RadarScan { x, y });
+                }
+            }
+        }
+        if holds(ActionType::Reinforce) {
+            for x in 0..board.height {
+                for y in 0..board.width {
+                    if board.cells[x][y] == Cells::Occupied {
+                        actions.push(Action::Reinforce { x, y });
+                    }
+                }
+            }
+        }
+        // A ship selected with start_patrol has already spent the card
+        if holds(ActionType::Patrol) || player.patrol_mode {
+            for ship in 0..player.base.ships.len() {
+                if player.check_can_patrol(ship).is_err() {
+                    continue;
+                }
+                for dir in Direction::ALL {
+                    let (dir_x, dir_y) = dir.delta();
+                    if player.patrol_destination(ship, dir_x, dir_y).is_ok() {
+                        actions.push(Action::Patrol { ship, dir });
+                    }
+                }
+            }
+        }
+        actions
+    }
+
+    /// Checks the current player may take `action` this turn and returns who they are.
+    fn check_can_act(&self, action: ActionType) -> Result<PlayerId, ActionError> {
+        let id = self.current().ok_or(ActionError::NotYourTurn)?;
+        if self.acted {
+            return Err(ActionError::AlreadyActed);
+        }
+        if self.rules.mode == Mode::Classic && action != ActionType::Missile {
+            return Err(ActionError::TwistOnly);
+        }
+        Ok(id)
+    }
+
+    /// Runs one action for the current player.
+    /// In Twist mode the card must be in hand first and is only spent if the action succeeds.
+    fn act<T>(&mut self, card: ActionType, action: impl FnOnce(&mut TwistPlayer, &mut TwistPlayer, &mut EventBus) -> Result<T, ActionError>) -> Result<T, ActionError> {
+        let id = self.check_can_act(card)?;
+        let twist = self.rules.mode == Mode::Twist;
+
+        let (player, opponent) = pair_mut(&mut self.players, id);
+        if twist {
+            player.has_card(card)?;
+        }
+        let result = action(player, opponent, &mut self.events)?;
+        if twist {
+            pair_mut(&mut self.players, id).0.use_card(card, &mut self.events)?;
+        }
+
+        self.acted = true;
+        Ok(result)
+    }
+
+    /// Ends the game if the current player has sunk the last opposing ship.
+    fn check_win(&mut self) {
+        if let Some(id) = self.current() {
+            if self.player(id.other()).base.ship_count == 0 {
+                self.phase = Phase::GameOver { winner: id, turns: self.turn };
+                self.events.emit(GameEvent::GameWon { winner: id, turns: self.turn });
+            }
+        }
+    }
+}
+
+impl CountedRng {
+    /// The stream from `seed` with the first `words` words already used.
+    fn new(seed: u64, words: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..words {
+            rng.next_u32();
+        }
+        CountedRng { seed, words, rng }
+    }
+}
+
+/* ------ Trait Implementations ------ */
+impl RngCore for CountedRng {
+    fn next_u32(&mut self) -> u32 {
+        self.words += 1;
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.words += 2;
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        self.words += dst.len().div_ceil(4) as u64;
+        self.rng.fill_bytes(dst)
+    }
+}
+
+/* ------ Functions ------ */
+/// Mutable access to a player and their opponent at the same time.
+/// Takes the players rather than the game so the event bus can still be borrowed alongside them.
+fn pair_mut(players: &mut [TwistPlayer; 2], id: PlayerId) -> (&mut TwistPlayer, &mut TwistPlayer) {
+    let (first, second) = players.split_at_mut(1);
+    match id {
+        PlayerId::One => (&mut first[0], &mut second[0]),
+        PlayerId::Two => (&mut second[0], &mut first[0]),
+    }
+}
+
+/* ------ Tests ------ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 5x5 game where each player has a single Destroyer, so a game can be won in a few shots
+    fn small_game(mode: Mode) -> Game {
+        let fleet = Fleet::new().with(FleetEntry::new(ShipType::Destroyer, 2, 1));
+        Game::with_seed(Rules::new(mode).with_board(5, 5).with_fleet(fleet), 7).unwrap()
+    }
+
+    /// A cell of `id`'s board that isn't one of their ships
+    fn water(game: &Game, id: PlayerId) -> (usize, usize) {
+        let board = &game.player(id).base.board;
+        (0..5).flat_map(|x| (0..5).map(move |y| (x, y)))
+            .find(|&(x, y)| board.cells[x][y] == Cells::Empty)
+            .unwrap()
+    }
+
+    #[test]
+    fn phases_follow_the_turns() {
+        let mut game = small_game(Mode::Classic);
+        assert_eq!(game.phase, Phase::Setup);
+        assert_eq!(game.current(), None);
+
+        game.start();
+        assert_eq!(game.phase, Phase::Turn(PlayerId::One));
+        let (x, y) = water(&game, PlayerId::Two);
+        game.apply(Action::Missile { x, y }).unwrap();
+        assert_eq!(game.apply(Action::Missile { x: 0, y: 0 }), Err(ActionError::AlreadyActed));
+        game.apply(Action::EndTurn).unwrap();
+        assert_eq!(game.phase, Phase::Handoff);
+        assert_eq!(game.apply(Action::EndTurn), Err(ActionError::NotYourTurn));
+
+        game.begin_turn();
+        assert_eq!(game.phase, Phase::Turn(PlayerId::Two));
+        assert_eq!(game.turn, 1);
+        let (x, y) = water(&game, PlayerId::One);
+        game.apply(Action::Missile { x, y }).unwrap();
+        game.apply(Action::EndTurn).unwrap();
+        game.begin_turn();
+        assert_eq!(game.phase, Phase::Turn(PlayerId::One));
+        assert_eq!(game.turn, 2);
+    }
+
+    #[test]
+    fn turn_cant_end_before_acting() {
+        let mut game = small_game(Mode::Classic);
+        game.start();
+        assert!(!game.can_end_turn());
+        assert_eq!(game.end_turn(), Err(ActionError::MustActFirst));
+        assert_eq!(game.phase, Phase::Turn(PlayerId::One));
+    }
+
+    #[test]
+    fn sinking_the_last_ship_wins() {
+        let mut game = small_game(Mode::Classic);
+        game.start();
+        let targets = game.player(PlayerId::Two).base.ships[0].positions.clone();
+        let miss = water(&game, PlayerId::One);
+
+        let (x, y) = targets[0];
+        game.apply(Action::Missile { x, y }).unwrap();
+        game.apply(Action::EndTurn).unwrap();
+        game.begin_turn();
+        game.apply(Action::Missile { x: miss.0, y: miss.1 }).unwrap();
+        game.apply(Action::EndTurn).unwrap();
+        game.begin_turn();
+        let (x, y) = targets[1];
+        game.apply(Action::Missile { x, y }).unwrap();
+
+        assert_eq!(game.phase, Phase::GameOver { winner: PlayerId::One, turns: 2 });
+        assert!(game.legal_actions(PlayerId::One).is_empty());
+        assert_eq!(game.apply(Action::EndTurn), Err(ActionError::NotYourTurn));
+    }
+
+    #[test]
+    fn classic_offers_every_untried_cell_then_the_end_of_the_turn() {
+        let mut game = small_game(Mode::Classic);
+        game.start();
+        let legal = game.legal_actions(PlayerId::One);
+        assert_eq!(legal.len(), 25);
+        assert!(legal.iter().all(|action| matches!(action, Action::Missile { .. })));
+        assert!(game.legal_actions(PlayerId::Two).is_empty());
+
+        game.apply(legal[0]).unwrap();
+        assert_eq!(game.legal_actions(PlayerId::One), vec![Action::EndTurn]);
+    }
+
+    #[test]
+    fn twist_only_offers_cards_in_hand() {
+        let mut game = small_game(Mode::Twist);
+        game.start();
+        let hand = game.player(PlayerId::One).hand.clone();
+        for action in game.legal_actions(PlayerId::One) {
+            assert!(action.card().is_some_and(|card| hand.contains(&card)), "{:?} isn't in {:?}", action, hand);
+        }
+
+        game.players[0].hand = vec![ActionType::Torpedo; HAND_SIZE];
+        let legal = game.legal_actions(PlayerId::One);
+        assert_eq!(legal, (0..5).map(|col| Action::Torpedo { col }).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn twist_pass_swaps_the_hand() {
+        let mut game = small_game(Mode::Twist);
+        game.start();
+        // Nothing left to reinforce, so a hand of Reinforce cards can't be played
+        let ship = game.player(PlayerId::One).base.ships[0].positions.clone();
+        for (x, y) in ship {
+            game.players[0].base.board.cells[x][y] = Cells::Reinforced;
+        }
+        let stuck = vec![ActionType::Reinforce; HAND_SIZE];
+        game.players[0].hand = stuck.clone();
+        assert_eq!(game.legal_actions(PlayerId::One), vec![Action::EndTurn]);
+
+        game.apply(Action::EndTurn).unwrap();
+        assert_eq!(game.phase, Phase::Handoff);
+        assert_eq!(game.player(PlayerId::One).hand.len(), HAND_SIZE);
+        assert_ne!(game.player(PlayerId::One).hand, stuck);
+    }
+}
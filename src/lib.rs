@@ -0,0 +1,23 @@
+//! The Battleship game rules without any graphics.
+//! `base` holds the classic rules and `twist` adds the action cards on top.
+//! The macroquad window in `main.rs` only reads these states and draws them,
+//! so the rules can also be used headless for simulations, bots and tests.
+
+/* ------ Modules ------ */
+pub mod base; // Classic mode rules
+pub mod twist; // Twist mode rules
+pub mod error; // Errors returned by player actions
+pub mod events; // Feedback emitted by player actions
+pub mod action; // Everything a player can do as one value
+pub mod game; // Turn flow and win detection
+pub mod rules; // Settings chosen before a game starts
+pub mod placement; // Random fleet placement and layout strategies
+pub mod ai; // Computer opponents
+pub mod search; // Monte Carlo search player
+pub mod save; // Saving a game part way through and carrying it on later
+pub mod replay; // Recording a whole match to play it back later
+pub mod notation; // Writing a match down as text and reading it back
+pub mod diagram; // Boards drawn as text, for hand-made fleets and positions
+pub mod profile; // What the computer learns about each person between games
+pub mod engine; // External engines over stdin and stdout
+pub mod sim; // Headless matches between computer players
@@ -1,37 +1,136 @@
 //! Handles game loop logic, ui drawing and input
-//! Calls into `base.rs` for classic mode logic and `twist.rs` for twist mode logic
+//! Turn flow and the rules live in `game.rs` (with `base.rs` and `twist.rs` underneath),
+//! this loop turns input into game actions and calls into `ui.rs` to draw the current player's boards
 
  /* ------ Import Used Libraries ------ */
-mod base; // Imports the base game module
-mod twist; // Imports the twist game module
-
-// Uses base game module
-use base::*; 
+mod ui; // Imports the drawing module
+mod viewer; // Imports the replay viewer
+
+// Uses the game rules
+use battleships::action::*;
+use battleships::ai::*;
+use battleships::base::*;
+use battleships::diagram::{load_fleet, write_board, write_guesses};
+use battleships::engine::{Engine, DEFAULT_TIME_LIMIT};
+use battleships::events::*;
+use battleships::game::*;
+use battleships::notation::is_notation;
+use battleships::placement::Strategy;
+use battleships::profile::{HabitRecorder, Profiles, DEFAULT_PROFILE, PROFILE_FILE};
+use battleships::rules::*;
+use battleships::replay::{Replay, ReplayRecorder, REPLAY_FILE};
+use battleships::save::{SavedGame, Seat, SAVE_FILE};
+use battleships::search::{Budget, DEFAULT_THINK_TIME};
+use battleships::twist::ActionType;
+// Uses the drawing module
+use ui::*;
 // Graphics library
-use macroquad::{audio, prelude::*}; 
-// A module I recompiled and made small fixes to, but did not write. Used for grid graphics and logic.
-// Origional code avaliable here: https://github.com/TheDinner22/macroquad_grid
-extern crate macroquad_grid_dex;
-
-// Conditionaly uses the twist module
-#[cfg(feature = "twist")] 
-use twist::*;
+use macroquad::{audio, prelude::*};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 /*------ Constants ------ */
 // Sound Effects Constants (Bytes needed for succesful compile)
-#[cfg(feature = "twist")] 
 const REINFORCE_SOUND: &[u8] = include_bytes!("Sound/Reinforce(new version).wav");
 
-#[cfg(feature = "twist")] 
 const SONAR_SOUND: &[u8] = include_bytes!("Sound/Sonar(new version).wav");
 
 const MISSLE_SOUND: &[u8] = include_bytes!("Sound/Sound Effect - Missile Launch.wav");
 
 const SPLASH_SOUND: &[u8] = include_bytes!("Sound/Splash(new version).wav");
 
-#[cfg(feature = "twist")] 
 const TORPEDO_SOUND: &[u8] = include_bytes!("Sound/Torpedo(new version).wav");
 
+const USAGE: &str = "Usage: battleships [--board WxH] [--fleet classic|eastern] [--no-touching] [--seed N] [--layout LAYOUT]
+  [--ai [LEVEL] | --engine COMMAND | --hotseat] [--time-limit MS] [--iterations N | --think-time MS] [--advisor] [--profile NAME]
+  [--resume [FILE]] [--record [FILE]] [--replay [FILE]] [--fleet-file FILE] [--fleet-file-2 FILE]";
+
+const COMPUTER: PlayerId = PlayerId::Two; // Seat the computer plays in single player
+const COMPUTER_DELAY: f32 = 0.5;          // Seconds between the computer's actions, so they can be followed
+const BOARD_FILE: &str = "board.txt";     // Where D writes the shown player's board
+const GUESSES_FILE: &str = "guesses.txt"; // Where D writes the shown player's guess board
+
+// Which rules this build plays
+#[cfg(feature = "twist")]
+const MODE: Mode = Mode::Twist;
+#[cfg(not(feature = "twist"))]
+const MODE: Mode = Mode::Classic;
+
+/*------ Enums and Structs ------ */
+/// How this run was started from the command line
+struct Settings {
+    rules: Rules,             // Board, fleet and mode
+    seed: Option<u64>,        // Seed to deal the game from, a random one if not given
+    layout: Strategy,         // How human players' fleets are laid out
+    player_two: Option<Seat>, // Who plays Player 2, asked on the start screen if not given
+    time_limit: Duration,     // Time an engine gets for each move
+    budget: Budget,           // Time the Master computer gets to think about each move
+    advisor: bool,            // Whether the shot advisor starts switched on
+    profile: String,          // Name the computer remembers the human's habits under
+    resume: Option<String>,   // Save file to carry on from instead of starting a new game
+    record: Option<String>,   // File to write a replay of the game to
+    replay: Option<String>,   // Replay to watch instead of playing
+    drawn_fleets: [Option<String>; 2], // Files with a fleet drawn out for Player 1 and Player 2, instead of laying it out by strategy
+}
+
+/// A computer player thinking on its own thread, so the window keeps drawing while an engine or the search takes its time.
+struct Thinker {
+    requests: Option<Sender<GameState>>, // Positions to pick an action for, closed to stop the thread
+    replies: Receiver<Action>,           // Picked actions, in the order they were asked for
+    waiting: bool,                       // Whether an action has been asked for and hasn't come back yet
+    worker: Option<JoinHandle<()>>,      // The thread, joined when the thinker is dropped
+}
+
+/* ------ Struct Implementations ------ */
+impl Thinker {
+    /// Moves `ai` onto its own thread to play `id`.
+    fn new(mut ai: Box<dyn Opponent>, id: PlayerId) -> Self {
+        let (requests, positions) = channel::<GameState>();
+        let (answers, replies) = channel();
+        let worker = thread::spawn(move || {
+            // The copy has no subscribers, an engine still hears about the game from the real one
+            for state in positions {
+                let action = Game::from_state(state).map_or(Action::Resign, |game| ai.choose_action(&game, id));
+                if answers.send(action).is_err() {
+                    break;
+                }
+            }
+        });
+        Thinker { requests: Some(requests), replies, waiting: false, worker: Some(worker) }
+    }
+
+    /// The action picked for the game as it is now, None while it's still thinking. Never waits.
+    fn poll(&mut self, game: &Game) -> Option<Action> {
+        if !self.waiting {
+            if let Some(requests) = &self.requests {
+                let _ = requests.send(game.state());
+            }
+            self.waiting = true;
+        }
+        match self.replies.try_recv() {
+            Ok(action) => {
+                self.waiting = false;
+                Some(action)
+            }
+            Err(TryRecvError::Empty) => None,
+            // The thread has gone, so the seat resigns like an engine that stopped running
+            Err(TryRecvError::Disconnected) => Some(Action::Resign),
+        }
+    }
+}
+
+/* ------ Trait Implementations ------ */
+/// Lets the player finish the move it's on and drop on its own thread, so an engine is told how the game ended and asked to quit.
+impl Drop for Thinker {
+    fn drop(&mut self) {
+        self.requests = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
 /*------ Main Loop ------ */
 // Change the title of the game window based of of the compile specifications
 #[cfg_attr(feature = "twist", macroquad::main("Battleship Twisted"))]
@@ -41,82 +140,199 @@ async fn main() {
 
     /* --- Loads Sound Assets --- */
     // Load sound effects from data
-    #[cfg(feature = "twist")] 
     let reinforce_sound: audio::Sound = audio::load_sound_from_bytes(REINFORCE_SOUND).await.unwrap();
-    #[cfg(feature = "twist")] 
     let torpedo_sound: audio::Sound = audio::load_sound_from_bytes(TORPEDO_SOUND).await.unwrap();
-    #[cfg(feature = "twist")] 
     let sonar_sound: audio::Sound = audio::load_sound_from_bytes(SONAR_SOUND).await.unwrap();
     let splash_sound: audio::Sound = audio::load_sound_from_bytes(SPLASH_SOUND).await.unwrap();
     let missile_sound: audio::Sound = audio::load_sound_from_bytes(MISSLE_SOUND).await.unwrap();
 
-    /* --- Initialise Players --- */
-    #[cfg(feature = "twist")] 
-    let mut player1: TwistPlayer = {
-        let base = BasePlayer::new();
-        TwistPlayer::new(base)
+    let settings = match settings_from_args(std::env::args().skip(1)) {
+        Ok(settings) => settings,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            std::process::exit(2);
+        }
     };
+    // Where Q saves the game, written down games are carried on from but not saved over
+    let save_path = settings.resume.clone().filter(|path| !is_notation(path)).unwrap_or_else(|| SAVE_FILE.to_string());
+
+    /* --- Watch Replay --- */
+    if let Some(path) = &settings.replay {
+        let watched = match Replay::load(path) {
+            Ok(replay) => viewer::watch(&replay).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = watched {
+            eprintln!("{}", e);
+        }
+        return;
+    }
 
-    #[cfg(feature = "twist")]
-    let mut player2: TwistPlayer = {
-        let base = BasePlayer::new();
-        TwistPlayer::new(base)
+    let (mut game, player_two, saved_replay) = match &settings.resume {
+        /* --- Resume Written Down Game --- */
+        // Played on from its last position, with the game so far as the start of its replay
+        Some(path) if is_notation(path) => match Replay::load(path).map_err(|e| e.to_string()).and_then(|replay| replay.play().map(|game| (game, replay)).map_err(|e| e.to_string())) {
+            Ok((game, replay)) => (game, replay.player_two.clone(), Some(replay)),
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        },
+        /* --- Resume Saved Game --- */
+        // A saved game brings its own rules and Player 2
+        Some(path) => match SavedGame::load(path).map_err(|e| e.to_string()).and_then(|saved| saved.resume().map_err(|e| e.to_string())) {
+            Ok(resumed) => resumed,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        },
+        None => {
+            /* --- Choose Opponent --- */
+            let player_two = match settings.player_two {
+                Some(seat) => seat,
+                None => match choose_player_two().await {
+                    Some(seat) => seat,
+                    None => return, // Escape on the start screen
+                },
+            };
+
+            /* --- Initialise Game --- */
+            let seed = settings.seed.unwrap_or_else(::rand::random);
+            // The computer lays its fleet out in one of its level's styles
+            let layouts = match &player_two {
+                Seat::Human => [settings.layout; 2],
+                Seat::Computer(level) => [settings.layout, level.layout(seed)],
+                Seat::Engine(_) => [settings.layout, Strategy::Random],
+            };
+            match Game::with_layouts(settings.rules, seed, layouts) {
+                Ok(game) => (game, player_two, None),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            }
+        }
+    };
+    let event_feed = game.events.subscribe(); // The ui's copy of the feedback
+
+    // The computer player in single player, seeded from the game so a seeded game replays exactly
+    let mut computer: Option<Box<dyn Opponent>> = match &player_two {
+        Seat::Human => None,
+        Seat::Computer(level) => Some(level.opponent_with_budget(game.seed, settings.budget)),
+        Seat::Engine(command) => match Engine::spawn(command, settings.time_limit) {
+            // A resumed game is told to the engine from the first move, which only a recording has
+            Ok(mut engine) if settings.resume.is_some() => match &saved_replay {
+                Some(replay) if replay.start.phase == Phase::Setup => match engine.catch_up(replay, COMPUTER) {
+                    Ok(()) => Some(Box::new(engine)),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                },
+                _ => {
+                    eprintln!("An engine can only carry on a game that was recorded from the start, save it with --record to resume it");
+                    return;
+                }
+            },
+            Ok(engine) => Some(Box::new(engine)),
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        },
     };
 
-    #[cfg(not(feature = "twist"))] 
-    let mut player1: BasePlayer = BasePlayer::new();
-    
-    #[cfg(not(feature = "twist"))]
-    let mut player2: BasePlayer = BasePlayer::new();
+    // Levels that learn get a head start from how this person has played before, and watch this game too.
+    // A resumed game is already under way, so there's no fleet left to lay out or whole game to learn from
+    let mut learning = None;
+    if let (Seat::Computer(level), Some(ai)) = (&player_two, computer.as_mut()) {
+        if level.learns() && game.phase == Phase::Setup {
+            match Profiles::load(PROFILE_FILE) {
+                Ok(profiles) => {
+                    if let Some(habits) = profiles.habits(&settings.profile, game.rules.width, game.rules.height) {
+                        if let Err(e) = habits.prepare(&mut game, COMPUTER, *level, ai.as_mut()) {
+                            eprintln!("{}", e);
+                        }
+                    }
+                    learning = Some((profiles, HabitRecorder::new(&mut game, PlayerId::One)));
+                }
+                // Carry on without it, rather than overwrite a file that might just need fixing
+                Err(e) => eprintln!("{}, this game won't be remembered", e),
+            }
+        }
+    }
+    // Fleets drawn out by hand replace the laid out ones, a resumed game already has its fleets
+    for (id, path) in [PlayerId::One, PlayerId::Two].into_iter().zip(&settings.drawn_fleets) {
+        if let (Some(path), Phase::Setup) = (path, game.phase) {
+            match load_fleet(&game.rules, path) {
+                Ok((board, ships)) => game.set_board(id, board, ships),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            }
+        }
+    }
+    // Only now are both fleets settled, so the computer is told the ones it will actually play with
+    if let Some(ai) = computer.as_mut() {
+        ai.join(&mut game, COMPUTER);
+    }
+
+    // A game saved while being recorded carries on recording, otherwise --record starts from here
+    let replay_path = settings.record.clone().unwrap_or_else(|| REPLAY_FILE.to_string());
+    let mut recorder = match saved_replay {
+        Some(replay) => Some(ReplayRecorder::resume(&mut game, replay)),
+        None if settings.record.is_some() => Some(ReplayRecorder::new(&mut game, player_two.clone())),
+        None => None,
+    };
+    game.start();
+    let mut computer = computer.map(|ai| Thinker::new(ai, COMPUTER));
 
      /*--- Initialise Variables --- */
-     #[cfg(feature = "twist")] // Twist specific variable
-     const NO_ACTION_ERROR: &str = "You can't use that action, it isn't in your hand.";
-    
-    let mut player_turn: GameState = GameState::Player1; // Handles whose turn it is and whose it was last
-    let mut game_state: GameState = GameState::Player1; // Handles turns but also handles inbetween turns.
+    let mut view = PlayerView::new(game.rules.width, game.rules.height); // Grids for whoever's turn it is
+    let mut messages = MessageLog::new(); // Recent feedback shown on screen
+    let mut computer_timer = 0.0; // Time since the computer last acted
+    let mut saved = None; // Where Q saved the game, it is carried on from there rather than finished
 
     let mut show_help = false;
-
-    let mut player_acted: bool = false; // If the player acted that turn
-
-    let mut turncounter: f64 = 1.0; // Number of turns elapsed 
-
-    let mut player_won: GameState = GameState::Else; // Which player has won
+    let mut show_advisor = settings.advisor;
+    let mut advice: Option<(PlayerId, Advice)> = None; // Worked out once per change to the game rather than every frame
 
     /* ------ Game Loop ------ */
     loop {
         clear_background(BLACK); // Clears screen to black
 
-        // Set current player and opponent
-        let (mut current_player, mut current_opponent) = if game_state == GameState::Player1 {
-            (&mut player1, &mut player2)
-        } else {
-            (&mut player2, &mut player1)
-        };
-
         /* --- UI Drawing --- */
-        if game_state == GameState::Else {
-            // Inbetween UI
-            draw_text("Press Space to change player",(screen_width()/2.0)-350.0,(screen_height()/2.0)-30.0,60.0,WHITE);
-        }
-        else {
-            // Player UI
-            #[cfg(not(feature = "twist"))] 
-            {
-                current_player.boardgrid.draw();
-                current_player.guessgrid.draw();
-            }
-
-            draw_text("Player 1's turn", (screen_width()/2.0)-100.0, 45.0, 30.0, WHITE);
-        
-            #[cfg(feature = "twist")] 
-            {
-                current_player.base.boardgrid.draw();
-                current_player.base.guessgrid.draw();
+        match game.phase {
+            Phase::Turn(id) => {
+                // Player UI
+                game.tick();
+
+                // Against the computer you always see your own boards
+                let shown = if computer.is_some() { PlayerId::One } else { id };
+                let player = game.player(shown);
+                view.sync_twist(player);
+                view.draw();
+                if show_advisor {
+                    if !matches!(advice, Some((advised, _)) if advised == shown) {
+                        advice = Some((shown, Advice::new(&game, shown)));
+                    }
+                    if let Some((_, advice)) = &advice {
+                        view.draw_advice(advice);
+                    }
+                }
 
-                current_player.update_patrol();
-                draw_hand_to_screen(&current_player.hand, (screen_width()/2.0)-120.0, 500.0);
+                let title = if computer.is_some() && id == COMPUTER { "Computer's turn".to_string() } else { format!("{}'s turn", id) };
+                draw_text(title.as_str(), (screen_width()/2.0)-100.0, 45.0, 30.0, WHITE);
+                if game.rules.mode == Mode::Twist {
+                    draw_hand_to_screen(&player.hand, (screen_width()/2.0)-120.0, 500.0);
+                }
+                messages.draw(150.0, 640.0);
+            }
+            _ => {
+                // Inbetween UI
+                draw_text("Press Space to change player",(screen_width()/2.0)-350.0,(screen_height()/2.0)-30.0,60.0,WHITE);
             }
         }
 
@@ -124,264 +340,219 @@ async fn main() {
             draw_help_menu();
         }
 
-        /* --- Input for the Twisted Version --- */
-        #[cfg(feature = "twist")] 
-        {
-            // Stops if the player has acted
-            if !player_acted && game_state != GameState::Else{ 
-
-                /* --- Missile Action --- */
-                if is_mouse_button_pressed(MouseButton::Left) {
-                    // If the current player has the card 
-                    if current_player.use_card(ActionType::Missile) {
-                        // Gets the grid pos of where the mouse was when clicked
-                        if let Some((x,y)) = current_player.base.get_clicked_cell() {
-                            // If you hit or not
-                            let hit = current_player.base.fire_missile(&mut current_opponent.base, x, y);
-
-                            player_acted = true;
-
-                            // player feedback
-                            println!("Missile {}", if hit { "hit!"} else { "missed."});
-                            if hit {audio::play_sound_once(&missile_sound)} else {audio::play_sound_once(&splash_sound)};  
-                        } else {
-                            // If didn't click on a grid return the missile to hand
-                            current_player.hand.push(ActionType::Missile);
-                        }
-                    } else {
-                        // If the player didn't have the action
-                        println!("{}",NO_ACTION_ERROR);
-                    }
-                }
-
-                /* --- Torpedo Action --- */
-                if is_key_pressed(KeyCode::T) {
-                    if current_player.use_card(ActionType::Torpedo) {
-                        // Audio feedback
-                        audio::play_sound_once(&torpedo_sound);
-
-                        // Gets the grid pos of where the mouse was when 't' was pressed
-                        if let Some(target_x) = current_player.get_torpedo_target_column(){
-                            // If you hit or not
-                            let hit = current_player.fire_torpedo(&mut current_opponent, target_x);
+        /* --- Input --- */
+        // Every key and click becomes an Action, the game decides whether it is allowed
+        let human_turn = game.current().filter(|&id| computer.is_none() || id != COMPUTER);
+        if let Some(id) = human_turn {
+            let mut action = None;
 
-                            player_acted = true;
-
-                            // Text feedback
-                            println!("Torpedo {}", if hit { "hit!" } else { "missed." });
-                        } else {
-                            // If didn't click on a grid return the torpedo to hand
-                            current_player.hand.push(ActionType::Torpedo);
-                        }
-                    } else {
-                        // If the player didn't have the action
-                        println!("{}",NO_ACTION_ERROR);
-                    }
-                }
+            /* --- Missile Action --- */
+            if is_mouse_button_pressed(MouseButton::Left) {
+                // Gets the grid pos of where the mouse was when clicked
+                action = view.get_clicked_cell().map(|(x, y)| Action::Missile { x, y });
+            }
 
-                /*--- Reinforce Action --- */
-                if is_key_pressed(KeyCode::R) {
-                    if current_player.use_card(ActionType::Reinforce) {
-                        // Gets the grid pos of where the mouse was when 'r' was pressed
-                        if let Some((x,y)) = current_player.get_clicked_cell_on_own_board() {
-                            // If the cell was reinforced
-                            let success = current_player.reinforce(x,y);
-
-                            player_acted = true;
-
-                            // Player feedback
-                            println!("Reinforcement {}", if success { "successful!" } else { "failed." });
-                            if success { audio::play_sound_once(&reinforce_sound)}                             
-                        } else {
-                            // If didn't click on an occupied cell return the reinforce card to hand
-                            current_player.hand.push(ActionType::Reinforce);
-                        }
-                    } else {
-                        // If the player didn't have the action
-                        println!("{}",NO_ACTION_ERROR);
-                    }
-                }
+            /* --- Torpedo Action --- */
+            if is_key_pressed(KeyCode::T) {
+                // Gets the grid pos of where the mouse was when 't' was pressed
+                action = view.get_torpedo_target_column().map(|col| Action::Torpedo { col });
+            }
 
-                /* --- Radar Scan Action ---*/
-                if is_key_pressed(KeyCode::S) {
-                    if current_player.use_card(ActionType::RadarScan) {
-                        // Gets the grid pos of where the mouse was when 's' was pressed
-                        if let Some((x,y)) = current_player.base.get_clicked_cell() {
-                            current_player.radar_scan(&mut current_opponent,x,y);
+            /*--- Reinforce Action --- */
+            if is_key_pressed(KeyCode::R) {
+                // Gets the grid pos of where the mouse was when 'r' was pressed
+                action = view.get_clicked_cell_on_own_board().map(|(x, y)| Action::Reinforce { x, y });
+            }
 
-                            player_acted = true;
+            /* --- Radar Scan Action ---*/
+            if is_key_pressed(KeyCode::S) {
+                // Gets the grid pos of where the mouse was when 's' was pressed
+                action = view.get_clicked_cell().map(|(x, y)| Action::RadarScan { x, y });
+            }
 
-                            // Audio feedback
-                            audio::play_sound_once(&sonar_sound);
-                        } else {
-                            // If didn't click on board return radar scan to hand
-                            current_player.hand.push(ActionType::RadarScan);
-                        }
-                    } else {
-                        // If the player didn't have the action
-                        println!("{}",NO_ACTION_ERROR);
+            /*--- Patrol Selection --- */
+            // Picking the ship isn't an action yet, the arrow key pressed next completes it
+            if is_key_pressed(KeyCode::P) && !game.player(id).patrol_mode {
+                // Gets the grid pos of where the mouse was when 'p' was pressed
+                if let Some((x,y)) = view.get_clicked_cell_on_own_board() {
+                    if let Err(e) = game.start_patrol(x, y) {
+                        messages.push(e.to_string());
                     }
+                } else {
+                    messages.push("No ship selected".to_string());
                 }
-
-                /*--- Patrol Action --- */
-                if is_key_pressed(KeyCode::P) {
-                    if !current_player.patrol_mode {
-                        if current_player.use_card(ActionType::Patrol) {
-                            // Gets the grid pos of where the mouse was when 'p' was pressed
-                            if let Some((x,y)) = current_player.get_clicked_cell_on_own_board(){
-                                let started = current_player.start_patrol(x,y);
-
-                                if !started {println!("Couldn't start patrol")}
-                            } else {
-                                // If didn't click on board return patrol to hand
-                                current_player.hand.push(ActionType::Patrol);
-                                println!("No ship selected");
-                            }
-                        } else {
-                            // If the player didn't have the action
-                            println!("{}",NO_ACTION_ERROR);
-                        }
-                    } 
-                }
-            } 
+            }
 
             /* --- Patrol Moving --- */
-            if !player_acted {
-                if current_player.patrol_mode {
-                    let dir = if is_key_pressed(KeyCode::Up) {
-                        Some((-1, 0))
-                    } else if is_key_pressed(KeyCode::Down) {
-                        Some((1,0))
-                    } else if is_key_pressed(KeyCode::Left) {
-                        Some((0, -1))
-                    } else if is_key_pressed(KeyCode::Right) {
-                        Some((0, 1))
-                    } else {
-                        None
-                    };
-
-                    if let Some((dir_x, dir_y)) = dir {
-                        let success = current_player.try_patrol_move(dir_x, dir_y);
-                        println!("Patrol move {}", if success { "successful!" } else { "failed."});
-                        player_acted = success;
-                    }
+            if let (true, Some(ship)) = (game.player(id).patrol_mode, game.player(id).patrol_ship) {
+                let dir = if is_key_pressed(KeyCode::Up) {
+                    Some(Direction::Up)
+                } else if is_key_pressed(KeyCode::Down) {
+                    Some(Direction::Down)
+                } else if is_key_pressed(KeyCode::Left) {
+                    Some(Direction::Left)
+                } else if is_key_pressed(KeyCode::Right) {
+                    Some(Direction::Right)
+                } else {
+                    None
+                };
+
+                if let Some(dir) = dir {
+                    action = Some(Action::Patrol { ship, dir });
                 }
             }
-        } 
-
-        /*--- Classic Mode input --- */
-        // Classic base mode input handleing
-        #[cfg(not(feature = "twist"))]{
-            if !player_acted && game_state != GameState::Else{
-                // Gets the grid pos of where the mouse was when it was clicked
-                if is_mouse_button_pressed(MouseButton::Left) {
-                    if let Some((x,y)) = current_player.get_clicked_cell() {
-                        let hit = current_player.fire_missile(&mut current_opponent, x, y);
 
-                        player_acted = true;
+            /*--- End Turn --- */
+            if is_key_pressed(KeyCode::Space) {
+                action = Some(Action::EndTurn);
+            }
 
-                        println!("Missile {}", if hit { "hit!" } else { "missed." });
-                        if hit { audio::play_sound_once(&missile_sound)} else { audio::play_sound_once(&splash_sound)};
+            // Tells the player why an action was refused
+            if let Some(action) = action {
+                match game.apply(action) {
+                    Ok(()) => {
+                        if let Some(recorder) = recorder.as_mut() {
+                            recorder.record(&game, id, action);
+                        }
                     }
+                    Err(e) => messages.push(e.to_string()),
                 }
             }
-        }
-	
-        /*--- Change Turn --- */
-        if is_key_pressed(KeyCode::Space) {
-            // Switch to inbetween screen if the player has acted
-            if player_acted {
-                println!("Player changed"); 
-                println!(" ");
-                
-                // Draws cards 
-                #[cfg(feature = "twist")]{
-                    if current_player.deck.deck_list.is_empty() {
-                        current_player.draw_hand();
+        } else if let (Some(thinker), Some(COMPUTER)) = (computer.as_mut(), game.current()) {
+            /*--- Computer Turn --- */
+            computer_timer += get_frame_time();
+            // Asked once the delay is up, an engine can then take up to its time limit while the window keeps drawing
+            if computer_timer >= COMPUTER_DELAY {
+                if let Some(action) = thinker.poll(&game) {
+                    computer_timer = 0.0;
+                    // Legal actions never fail, but don't let a bad one hold the game up
+                    let taken = match game.apply(action) {
+                        Ok(()) => Some(action),
+                        Err(_) => game.apply(Action::EndTurn).ok().map(|()| Action::EndTurn),
+                    };
+                    if let (Some(recorder), Some(action)) = (recorder.as_mut(), taken) {
+                        recorder.record(&game, COMPUTER, action);
                     }
-                    let newcard = current_player.draw_card().unwrap();
-                    current_player.hand.push(newcard);
                 }
-        
-                // Reset player_acted here
-                player_acted = false;
-                game_state = GameState::Else;
-                turncounter += 1.0;
-            } else {
+            }
+        } else if game.phase == Phase::Handoff {
+            /*--- Change Turn --- */
+            if computer.is_some() {
+                // Nothing to hide from the computer, so there's no handoff screen
+                game.begin_turn();
+            } else if is_key_pressed(KeyCode::Space) {
                 // Switch to next turn
-                if game_state == GameState::Else {
-                    // Switch turns and reset state
-                    if player_turn == GameState::Player1 {
-                        game_state = GameState::Player2;
-                        player_turn = GameState::Player2;
-                    } else {
-                        game_state = GameState::Player1;
-                        player_turn = GameState::Player1;
-                    }
-                    // Ensure player_acted is reset for the new turn
-                    player_acted = false;
+                game.begin_turn();
+                messages = MessageLog::new(); // The next player shouldn't see the last turn's cards and moves
+            }
+        }
+
+        /*--- Event Feedback --- */
+        // Every event is shown in the on-screen log and triggers its sound effect
+        for event in event_feed.try_iter() {
+            // The computer's cards stay hidden
+            if computer.is_some() && matches!(event, GameEvent::CardDrawn { player: COMPUTER, .. }) {
+                continue;
+            }
+            match &event {
+                GameEvent::ShotResolved { weapon: ActionType::Missile, shot, .. } => {
+                    if shot.outcome.is_hit() { audio::play_sound_once(&missile_sound) } else { audio::play_sound_once(&splash_sound) }
                 }
+                GameEvent::ShotResolved { weapon: ActionType::Torpedo, .. } => audio::play_sound_once(&torpedo_sound),
+                GameEvent::RadarScanned { .. } => audio::play_sound_once(&sonar_sound),
+                GameEvent::CellReinforced { .. } => audio::play_sound_once(&reinforce_sound),
+                _ => {}
             }
+            messages.push(event.to_string());
+            advice = None; // Something changed, so the advice may have too
         }
 
         if is_key_pressed(KeyCode::H) {
             show_help = !show_help;
         }
 
-        /*--- Win Check --- */
-        #[cfg(feature = "twist")]{
-            // Win check for twist (just added a .base)
-            if player1.base.ship_count == 0 {
-                player_won = GameState::Player2;
-                break;
-            }else if player2.base.ship_count == 0 {
-                player_won = GameState::Player1;
-                break;
+        if is_key_pressed(KeyCode::A) {
+            show_advisor = !show_advisor;
+        }
+
+        // Writes the boards on screen out as text, to share a position or set one up again later
+        if is_key_pressed(KeyCode::D) {
+            if let Phase::Turn(id) = game.phase {
+                let shown = if computer.is_some() { PlayerId::One } else { id };
+                let player = &game.player(shown).base;
+                let written = std::fs::write(BOARD_FILE, write_board(player)).and_then(|()| std::fs::write(GUESSES_FILE, write_guesses(player)));
+                match written {
+                    Ok(()) => messages.push(format!("{}'s board written to {} and guesses to {}", shown, BOARD_FILE, GUESSES_FILE)),
+                    Err(e) => messages.push(format!("The boards couldn't be written: {}", e)),
+                }
             }
         }
 
-        #[cfg(not(feature = "twist"))]{
-            // Win check for base
-            if player1.ship_count == 0 {
-                player_won = GameState::Player2;
-                break;
-            }else if player2.ship_count == 0 {
-                player_won = GameState::Player1;
-                break;
+        // Saves the game to carry on later with --resume, then quits
+        if is_key_pressed(KeyCode::Q) && game.phase != Phase::Setup {
+            match SavedGame::new(&game, player_two.clone(), recorder.as_ref().map(|recorder| recorder.replay().clone())).save(&save_path) {
+                Ok(()) => {
+                    saved = Some(save_path.clone());
+                    break;
+                }
+                Err(e) => messages.push(e.to_string()),
             }
         }
 
+        /*--- Win Check --- */
+        if let Phase::GameOver { .. } = game.phase {
+            break;
+        }
+
         // Allows early exit of game
         if is_key_pressed(KeyCode::Escape) {
             break;
         }
 
         // Displays turn UI
-        let temp_turncounter = (turncounter/2.0).floor();
-        draw_text(format!("Turn: {}", temp_turncounter).as_str(),75.0,45.0,30.0,WHITE);
+        draw_text(format!("Turn: {}", game.turn).as_str(),75.0,45.0,30.0,WHITE);
+        // The seed is shown so an interesting game can be played again with --seed
+        draw_text(format!("Seed: {}", game.seed).as_str(),75.0,screen_height()-15.0,20.0,GRAY);
 
         // Updates view
         next_frame().await;
     }
+    drop(computer); // An engine is told how the game ended and asked to quit
+
+    // The last screen only says what became of the game's files
+    messages = MessageLog::new();
+    match &saved {
+        // A saved game keeps its replay in the save, but isn't learned from: only games played from the start teach the computer
+        Some(path) => messages.push(format!("Game saved to {}, carry on with --resume {}", path, path)),
+        None => {
+            // Written however the game ended, an abandoned game can still be worth watching
+            if let Some(recorder) = recorder {
+                match recorder.replay().save(&replay_path) {
+                    Ok(()) => messages.push(format!("Replay written to {}", replay_path)),
+                    Err(e) => messages.push(e.to_string()),
+                }
+            }
 
-    // Halve turns to get the true number
-    turncounter = turncounter/2.;
-    turncounter = turncounter.floor(); // Round down
+            if let Some((mut profiles, recorder)) = learning {
+                recorder.finish(&game, profiles.habits_mut(&settings.profile, game.rules.width, game.rules.height));
+                if let Err(e) = profiles.save(PROFILE_FILE) {
+                    messages.push(e.to_string());
+                }
+            }
+        }
+    }
 
     // loop to display winner message and wait for exit
     loop{
 
         clear_background(BLACK);
 
-        // Checks if Player 1 has won and displays win message
-        if player_won == GameState::Player1 {
-            draw_text("Player 1 Won!!", (screen_width()/2.0)-200.0, screen_height()/2.0, 60.0, WHITE);
-        }
-
-        // Checks if Player 2 has won and display win message
-        else if player_won == GameState::Player2 {
-            draw_text("Player 2 Won!!", (screen_width()/2.0)-200.0, screen_height()/2.0, 60.0, WHITE);
+        // Displays who won and how many turns it took
+        if let Phase::GameOver { winner, turns } = game.phase {
+            draw_text(format!("{} Won!!", winner).as_str(), (screen_width()/2.0)-200.0, screen_height()/2.0, 60.0, WHITE);
+            draw_text(format!("After {} turns",turns).as_str(),(screen_width()/2.0)-180.0,(screen_height()/2.0)+50.0,30.0,WHITE);
         }
+        messages.draw(150.0, 640.0);
 
         // Wait for space key or escape to be pressed the exit the game
         if is_key_pressed(KeyCode::Space) {
@@ -392,9 +563,126 @@ async fn main() {
             break;
         }
 
-        // Displays how many turns it took to win
-        draw_text(format!("After {} turns",turncounter).as_str(),(screen_width()/2.0)-180.0,(screen_height()/2.0)+50.0,30.0,WHITE);
-        
         next_frame().await;
     }
-}
\ No newline at end of file
+}
+
+/* ------ Functions ------ */
+/// Builds the settings for this run from the command line.
+/// `--board 12x8` plays on a board 12 columns wide and 8 rows tall and `--fleet eastern` swaps in the
+/// Eastern-European fleet, `--no-touching` keeps ships from being placed next to each other,
+/// `--seed 1234` replays the game dealt from that seed, `--layout edge` lays human fleets out in that
+/// style, `--advisor` starts with the shot advisor switched on, `--profile NAME` picks whose habits the
+/// computer learns from and remembers, `--resume` carries on the game saved with Q (from `savegame.json`, or
+/// the file given after it, or plays on from a game written down in a `.txt` file), `--record` writes a replay of the
+/// game (to `replay.json`, or the file given after it, as notation if it ends in `.txt`), `--replay` watches one
+/// instead of playing (the same file by default), `--fleet-file layout.txt` and `--fleet-file-2 layout.txt` set
+/// Player 1's or Player 2's fleet from a drawing (see `diagram.rs`), `--ai` plays against the computer
+/// (optionally followed by a difficulty such as `--ai hard`), `--think-time 500` sets how many
+/// milliseconds the Master level gets a move (100 unless told otherwise) or `--iterations 5000` gives it that
+/// many playouts instead so a seeded game plays out the same way every time, `--engine "./my_bot --fast"` plays against
+/// an external engine given `--time-limit` milliseconds a move and `--hotseat` plays two people without
+/// asking on the start screen. Says what's wrong instead if an option is unknown or its value can't be read.
+fn settings_from_args(args: impl Iterator<Item = String>) -> Result<Settings, String> {
+    let mut args = args.peekable();
+    let mut rules = Rules::new(MODE);
+    let mut seed = None;
+    let mut layout = Strategy::Random;
+    let mut player_two = None;
+    let mut time_limit = DEFAULT_TIME_LIMIT;
+    let mut budget = Budget::Time(DEFAULT_THINK_TIME);
+    let mut advisor = false;
+    let mut profile = DEFAULT_PROFILE.to_string();
+    let mut resume = None;
+    let mut record = None;
+    let mut replay = None;
+    let mut drawn_fleets = [None, None];
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--board" => {
+                let size = value()?;
+                match size.split_once('x').map(|(w, h)| (w.parse(), h.parse())) {
+                    Some((Ok(width), Ok(height))) => rules = rules.with_board(width, height),
+                    _ => return Err(format!("Expected --board WIDTHxHEIGHT, got '{}'", size)),
+                }
+            }
+            "--fleet" => {
+                let name = value()?;
+                let fleet = Fleet::from_name(&name).ok_or(format!("Unknown fleet '{}', expected classic or eastern", name))?;
+                rules = rules.with_fleet(fleet);
+            }
+            "--no-touching" => rules = rules.with_no_touching(true),
+            "--seed" => {
+                let value = value()?;
+                seed = Some(value.parse().map_err(|_| format!("Expected --seed NUMBER, got '{}'", value))?);
+            }
+            "--layout" => {
+                let name = value()?;
+                layout = Strategy::from_name(&name)
+                    .ok_or(format!("Unknown layout '{}', expected random, edge, spread, clustered or anti-density", name))?;
+            }
+            "--ai" => {
+                // The level is optional, the next argument might be another option
+                let level = match args.next_if(|next| !next.starts_with("--")) {
+                    Some(name) => Difficulty::from_name(&name).ok_or(format!("Unknown difficulty '{}'", name))?,
+                    None => Difficulty::Normal,
+                };
+                player_two = Some(Seat::Computer(level));
+            }
+            "--engine" => player_two = Some(Seat::Engine(value()?)),
+            "--time-limit" => {
+                let value = value()?;
+                let millis = value.parse().map_err(|_| format!("Expected --time-limit MILLISECONDS, got '{}'", value))?;
+                time_limit = Duration::from_millis(millis);
+            }
+            "--iterations" => {
+                let value = value()?;
+                budget = Budget::Iterations(value.parse().map_err(|_| format!("Expected --iterations NUMBER, got '{}'", value))?);
+            }
+            "--think-time" => {
+                let value = value()?;
+                let millis = value.parse().map_err(|_| format!("Expected --think-time MILLISECONDS, got '{}'", value))?;
+                budget = Budget::Time(Duration::from_millis(millis));
+            }
+            "--hotseat" => player_two = Some(Seat::Human),
+            "--advisor" => advisor = true,
+            // The file is optional for these three, the next argument might be another option
+            "--resume" => resume = Some(args.next_if(|next| !next.starts_with("--")).unwrap_or_else(|| SAVE_FILE.to_string())),
+            "--record" => record = Some(args.next_if(|next| !next.starts_with("--")).unwrap_or_else(|| REPLAY_FILE.to_string())),
+            "--replay" => replay = Some(args.next_if(|next| !next.starts_with("--")).unwrap_or_else(|| REPLAY_FILE.to_string())),
+            "--fleet-file" => drawn_fleets[0] = Some(value()?),
+            "--fleet-file-2" => drawn_fleets[1] = Some(value()?),
+            "--profile" => profile = value()?,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+            _ => return Err(format!("Unexpected argument '{}'", arg)),
+        }
+    }
+
+    Ok(Settings { rules, seed, layout, player_two, time_limit, budget, advisor, profile, resume, record, replay, drawn_fleets })
+}
+
+/// Start screen asking who plays Player 2.
+/// 1 is a second person, 2 onwards are the computer from Easy up. Returns None if Escape is pressed.
+async fn choose_player_two() -> Option<Seat> {
+    let keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5, KeyCode::Key6];
+    loop {
+        clear_background(BLACK);
+        draw_opponent_menu(&Difficulty::ALL);
+
+        for (i, &key) in keys.iter().enumerate() {
+            if is_key_pressed(key) {
+                return match i {
+                    0 => Some(Seat::Human),
+                    _ => Difficulty::ALL.get(i - 1).map(|&level| Seat::Computer(level)),
+                };
+            }
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            return None;
+        }
+
+        next_frame().await;
+    }
+}
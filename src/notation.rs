@@ -0,0 +1,485 @@
+//! Writing a game down as plain text, the way chess games are written in PGN.
+//! A header of `[Key "value"]` lines gives everything needed to deal the same game again (the rules, the seed,
+//! how each fleet was laid out, where every ship started and any cells reinforced before the start), with `"` and
+//! `\` in a value escaped by a `\`. Every line after it is one round:
+//!
+//! ```text
+//! 12. P1 M C7 hit; P2 T 4 miss
+//! ```
+//!
+//! Cells are a row letter and a column number as in `cell_name`, and a torpedo gives its column number.
+//! A player's part of a round is `M cell` (missile), `T column` (torpedo), `S cell` (radar scan), `R cell`
+//! (reinforce), `P cell direction` (patrol the ship on that cell), `pass` or `resigns`. A turn with more than one
+//! move, such as a shot then resigning, has a part for each. The other player moving ends the turn, and the last
+//! part written says `end` if its turn was ended. Words after a move such as
+//! `hit`, `miss`, `sunk`, `stripped` or `stopped` are there for whoever reads it and are skipped when it is read
+//! back, the rules work them out again. Reading notation gives a `Replay`, so it can be watched or played on from.
+
+/* ------ Import Used Libraries ------ */
+use crate::action::{cell_name, parse_cell, Action, Direction};
+use crate::ai::Difficulty;
+use crate::base::{Cells, PlayerId, ShipType, ShotOutcome};
+use crate::error::{FileError, NotationError};
+use crate::events::GameEvent;
+use crate::game::{Game, Phase};
+use crate::placement::{fleet_from_ends, ShipEnds, Strategy};
+use crate::replay::{Replay, ReplayRecorder};
+use crate::rules::{Fleet, FleetEntry, Mode, Rules};
+use crate::save::Seat;
+
+/*------ Constants ------ */
+pub const NOTATION_EXTENSION: &str = ".txt"; // Replays saved to or loaded from a file ending in this are notation instead of JSON
+
+/*------ Enums and Structs ------ */
+/// One player's part of a round while it is being written down.
+struct Part {
+    round: u32,             // Round it was played in
+    player: PlayerId,       // Whose turn it was
+    actions: Vec<String>,   // Each move they made that turn written out, none if they passed
+    ended: bool,            // Whether they ended the turn
+}
+
+/* ------ Functions ------ */
+/// Whether a file at `path` is read and written as notation
+pub fn is_notation(path: &str) -> bool {
+    path.ends_with(NOTATION_EXTENSION)
+}
+
+/// Writes `replay` to `path` as notation, replacing what was there.
+pub fn save(replay: &Replay, path: &str) -> Result<(), FileError> {
+    let text = write(replay).map_err(|e| FileError::Write { path: path.to_string(), reason: e.to_string() })?;
+    std::fs::write(path, text).map_err(|e| FileError::Write { path: path.to_string(), reason: e.to_string() })
+}
+
+/// Reads the notation in `path` into a replay.
+pub fn load(path: &str) -> Result<Replay, FileError> {
+    let text = std::fs::read_to_string(path).map_err(|e| FileError::Read { path: path.to_string(), reason: e.to_string() })?;
+    read(&text).map_err(|e| FileError::Format { path: path.to_string(), reason: e.to_string() })
+}
+
+/// Writes a recorded game down. Only a recording from the very start can be, as the header deals the game again
+/// from its seed, and the recording is played through to find out what each move did.
+pub fn write(replay: &Replay) -> Result<String, NotationError> {
+    let start = &replay.start;
+    if start.phase != Phase::Setup {
+        return Err(NotationError::Unwritable("the recording starts part way through it".to_string()));
+    }
+
+    let mut parts: Vec<Part> = Vec::new();
+    let end = replay.play_through(replay.moves.len(), |step, events, _| {
+        let carries_on = parts.last().is_some_and(|part| part.round == step.turn && part.player == step.player && !part.ended);
+        if !carries_on {
+            parts.push(Part { round: step.turn, player: step.player, actions: Vec::new(), ended: false });
+        }
+        let part = parts.last_mut().expect("just added");
+        match step.action {
+            Action::EndTurn => part.ended = true,
+            action => part.actions.push(action_text(action, events)),
+        }
+    }).map_err(NotationError::Replay)?;
+
+    /* --- Header --- */
+    let rules = &start.rules;
+    let fleet_cells = |id: PlayerId| {
+        let ships = &start.players[if id == PlayerId::One { 0 } else { 1 }].base.ships;
+        ships.iter().map(|ship| ends_text(&ship.positions)).collect::<Vec<_>>().join(" ")
+    };
+    // Cells reinforced before the game started, such as a fleet drawn with `#`s
+    let reinforced_cells = |id: PlayerId| {
+        let board = &start.players[if id == PlayerId::One { 0 } else { 1 }].base.board;
+        let cells: Vec<String> = (0..board.height)
+            .flat_map(|x| (0..board.width).map(move |y| (x, y)))
+            .filter(|&(x, y)| board.cells[x][y] == Cells::Reinforced)
+            .map(|(x, y)| cell_name(x, y))
+            .collect();
+        cells.join(" ")
+    };
+    let result = match end.phase {
+        Phase::GameOver { winner, turns } => format!("{} won after {} turns", winner.short_name(), turns),
+        _ => "unfinished".to_string(),
+    };
+    let mut header = vec![
+        ("Mode", format!("{:?}", rules.mode).to_lowercase()),
+        ("Board", format!("{}x{}", rules.width, rules.height)),
+        ("Fleet", rules.fleet.name().map_or_else(|| fleet_text(&rules.fleet), str::to_string)),
+        ("NoTouching", if rules.no_touching { "yes" } else { "no" }.to_string()),
+        ("Seed", start.seed.to_string()),
+        ("Layouts", format!("{} {}", start.layouts[0].name(), start.layouts[1].name())),
+        ("Player2", seat_text(&replay.player_two)),
+        ("P1Fleet", fleet_cells(PlayerId::One)),
+        ("P2Fleet", fleet_cells(PlayerId::Two)),
+    ];
+    for (id, key) in [(PlayerId::One, "P1Reinforced"), (PlayerId::Two, "P2Reinforced")] {
+        let cells = reinforced_cells(id);
+        if !cells.is_empty() {
+            header.push((key, cells));
+        }
+    }
+    header.push(("Result", result));
+    let mut lines: Vec<String> = header.iter().map(|(key, value)| format!("[{} {}]", key, quoted(value))).collect();
+    lines.push(String::new());
+
+    /* --- Rounds --- */
+    let mut round: Option<(u32, Vec<String>)> = None;
+    for (i, part) in parts.iter().enumerate() {
+        // More than one move in a turn (patrol steps, or resigning after a shot) are written one after another
+        let player = part.player.short_name();
+        let mut written = if part.actions.is_empty() {
+            format!("{} pass", player)
+        } else {
+            part.actions.iter().map(|action| format!("{} {}", player, action)).collect::<Vec<_>>().join("; ")
+        };
+        // Every other part ends its turn, as the other player moves next
+        if i + 1 == parts.len() && part.ended && !part.actions.is_empty() {
+            written.push_str(" end");
+        }
+        match &mut round {
+            Some((number, written_so_far)) if *number == part.round => written_so_far.push(written),
+            _ => {
+                if let Some((number, written_so_far)) = round.take() {
+                    lines.push(format!("{}. {}", number, written_so_far.join("; ")));
+                }
+                round = Some((part.round, vec![written]));
+            }
+        }
+    }
+    if let Some((number, written_so_far)) = round {
+        lines.push(format!("{}. {}", number, written_so_far.join("; ")));
+    }
+
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Reads a game written by `write`, dealing it again from the header and playing every move through the rules.
+pub fn read(text: &str) -> Result<Replay, NotationError> {
+    let mut lines = text.lines().enumerate().peekable();
+
+    /* --- Header --- */
+    let mut header: Vec<(String, String)> = Vec::new();
+    while let Some(&(_, line)) = lines.peek() {
+        let line = line.trim();
+        if !line.is_empty() {
+            let Some(inner) = line.strip_prefix('[').and_then(|inner| inner.strip_suffix(']')) else { break };
+            let (key, value) = inner.split_once(' ').ok_or(NotationError::Header(format!("line '{}' has no value", line)))?;
+            let value = unquoted(value.trim()).ok_or(NotationError::Header(format!("line '{}' has a value that isn't in quotes", line)))?;
+            header.push((key.to_string(), value));
+        }
+        lines.next();
+    }
+    let value = |key: &str| header.iter().find(|(name, _)| name.eq_ignore_ascii_case(key)).map(|(_, value)| value.as_str());
+    let required = |key: &str| value(key).ok_or(NotationError::Header(format!("has no {} line", key)));
+    let unreadable = |key: &str, value: &str| NotationError::Header(format!("has {} '{}', which can't be read", key, value));
+
+    let mode = match required("Mode")?.to_ascii_lowercase().as_str() {
+        "classic" => Mode::Classic,
+        "twist" => Mode::Twist,
+        other => return Err(unreadable("Mode", other)),
+    };
+    let board = required("Board")?;
+    let (width, height) = match board.split_once('x').map(|(w, h)| (w.parse(), h.parse())) {
+        Some((Ok(width), Ok(height))) => (width, height),
+        _ => return Err(unreadable("Board", board)),
+    };
+    let fleet_name = required("Fleet")?;
+    let fleet = Fleet::from_name(fleet_name).or_else(|| parse_fleet(fleet_name)).ok_or(unreadable("Fleet", fleet_name))?;
+    let no_touching = match value("NoTouching").unwrap_or("no") {
+        "yes" => true,
+        "no" => false,
+        other => return Err(unreadable("NoTouching", other)),
+    };
+    let seed_text = required("Seed")?;
+    let seed = seed_text.parse().map_err(|_| unreadable("Seed", seed_text))?;
+    let layout_names = required("Layouts")?;
+    let layouts = match layout_names.split_whitespace().map(Strategy::from_name).collect::<Vec<_>>()[..] {
+        [Some(first), Some(second)] => [first, second],
+        _ => return Err(unreadable("Layouts", layout_names)),
+    };
+    let player_two = match value("Player2") {
+        Some(seat) => parse_seat(seat).ok_or(unreadable("Player2", seat))?,
+        None => Seat::Human,
+    };
+
+    let rules = Rules::new(mode).with_board(width, height).with_fleet(fleet).with_no_touching(no_touching);
+    let mut game = Game::with_layouts(rules, seed, layouts).map_err(NotationError::Start)?;
+    // The fleets are given in full, they may not have come from the layouts (the computer learning habits moves its own)
+    for (id, key) in [(PlayerId::One, "P1Fleet"), (PlayerId::Two, "P2Fleet")] {
+        let ships = required(key)?;
+        let ends = ships.split_whitespace().map(parse_ends).collect::<Option<Vec<_>>>().ok_or(unreadable(key, ships))?;
+        let ships = fleet_from_ends(&game.rules, &ends).map_err(NotationError::Start)?;
+        game.set_fleet(id, ships);
+    }
+    for (id, key) in [(PlayerId::One, "P1Reinforced"), (PlayerId::Two, "P2Reinforced")] {
+        if let Some(cells) = value(key) {
+            let mut board = game.player(id).base.board.clone();
+            for name in cells.split_whitespace() {
+                match parse_cell(name) {
+                    Some((x, y)) if board.in_bounds(x, y) && board.cells[x][y] == Cells::Occupied => board.cells[x][y] = Cells::Reinforced,
+                    _ => return Err(unreadable(key, cells)),
+                }
+            }
+            let ships = game.player(id).base.ships.clone();
+            game.set_board(id, board, ships);
+        }
+    }
+
+    /* --- Rounds --- */
+    let mut recorder = ReplayRecorder::new(&mut game, player_two);
+    game.start();
+    for (index, line) in lines {
+        let error = |reason: String| NotationError::Move { line: index + 1, reason };
+        // The round number is for whoever reads it, the game counts rounds itself
+        let line = match line.split_once('.') {
+            Some((round, rest)) if round.trim().parse::<u32>().is_ok() => rest,
+            _ => line,
+        };
+
+        for part in line.split(';') {
+            let words: Vec<&str> = part.split_whitespace().collect();
+            let Some((&name, words)) = words.split_first() else { continue };
+            let player = match name.to_ascii_uppercase().as_str() {
+                "P1" => PlayerId::One,
+                "P2" => PlayerId::Two,
+                _ => return Err(error(format!("expected P1 or P2, got '{}'", name))),
+            };
+
+            // The other player moving means the last turn was ended
+            if let Phase::Turn(current) = game.phase {
+                if current != player {
+                    take(&mut game, &mut recorder, current, Action::EndTurn, "end their turn").map_err(error)?;
+                }
+            }
+            if game.phase == Phase::Handoff {
+                game.begin_turn();
+            }
+            if game.current() != Some(player) {
+                return Err(error(format!("it isn't {}'s turn", player)));
+            }
+
+            let action = parse_action(&game, player, words).map_err(error)?;
+            take(&mut game, &mut recorder, player, action, &format!("play '{}'", words.join(" "))).map_err(error)?;
+            if action != Action::EndTurn && words.last() == Some(&"end") {
+                take(&mut game, &mut recorder, player, Action::EndTurn, "end their turn").map_err(error)?;
+            }
+        }
+    }
+
+    Ok(recorder.replay().clone())
+}
+
+/// Applies `player`'s `action` and records it, or says why they can't do `what`.
+fn take(game: &mut Game, recorder: &mut ReplayRecorder, player: PlayerId, action: Action, what: &str) -> Result<(), String> {
+    game.apply(action).map_err(|e| format!("{} can't {}: {}", player, what, e.to_string().trim_end_matches('.')))?;
+    recorder.record(game, player, action);
+    Ok(())
+}
+
+/// Writes down one move, with what it did from the events it caused.
+fn action_text(action: Action, events: &[GameEvent]) -> String {
+    let shot = events.iter().find_map(|event| match event {
+        GameEvent::ShotResolved { shot, .. } => Some(shot),
+        _ => None,
+    });
+    let result = shot.map_or("miss", |shot| match shot.outcome {
+        ShotOutcome::Miss => "miss",
+        ShotOutcome::Hit => "hit",
+        ShotOutcome::Sunk(_) => "sunk",
+        ShotOutcome::ReinforcementStripped => "stripped",
+        ShotOutcome::AlreadyHit => "stopped",
+    });
+
+    match action {
+        Action::Missile { x, y } => format!("M {} {}", cell_name(x, y), result),
+        Action::Torpedo { col } => match shot.and_then(|shot| shot.cells.last()) {
+            // Where a torpedo stopped is worth knowing when it found something
+            Some(&(x, y)) if result != "miss" => format!("T {} {} {}", col + 1, result, cell_name(x, y)),
+            _ => format!("T {} {}", col + 1, result),
+        },
+        Action::RadarScan { x, y } => format!("S {}", cell_name(x, y)),
+        Action::Reinforce { x, y } => format!("R {}", cell_name(x, y)),
+        Action::Patrol { dir, .. } => {
+            // Named by a cell it was on before the move
+            let (dir_x, dir_y) = dir.delta();
+            let from = events.iter().find_map(|event| match event {
+                GameEvent::PatrolMoved { positions, .. } => positions.first().copied(),
+                _ => None,
+            });
+            let from = from.map_or("?".to_string(), |(x, y)| cell_name((x as isize - dir_x) as usize, (y as isize - dir_y) as usize));
+            format!("P {} {}", from, format!("{:?}", dir).to_lowercase())
+        }
+        Action::EndTurn => "pass".to_string(),
+        Action::Resign => "resigns".to_string(),
+    }
+}
+
+/// Reads one move from the words after the player, anything after what the move needs is skipped.
+fn parse_action(game: &Game, player: PlayerId, words: &[&str]) -> Result<Action, String> {
+    let word = |i: usize| words.get(i).copied().ok_or(format!("'{}' is missing part of the move", words.join(" ")));
+    let cell = |i: usize| word(i).and_then(|name| parse_cell(name).ok_or(format!("'{}' isn't a cell", name)));
+
+    let kind = word(0)?.to_ascii_uppercase();
+    match kind.as_str() {
+        "M" => cell(1).map(|(x, y)| Action::Missile { x, y }),
+        "T" => {
+            let column = word(1)?;
+            match column.parse::<usize>() {
+                Ok(col) if col > 0 => Ok(Action::Torpedo { col: col - 1 }),
+                _ => Err(format!("'{}' isn't a column", column)),
+            }
+        }
+        "S" => cell(1).map(|(x, y)| Action::RadarScan { x, y }),
+        "R" => cell(1).map(|(x, y)| Action::Reinforce { x, y }),
+        "P" => {
+            let (x, y) = cell(1)?;
+            let dir_name = word(2)?;
+            let dir = Direction::ALL.into_iter()
+                .find(|dir| format!("{:?}", dir).eq_ignore_ascii_case(dir_name))
+                .ok_or(format!("'{}' isn't up, down, left or right", dir_name))?;
+            let ship = game.player(player).base.ships.iter()
+                .position(|ship| ship.positions.contains(&(x, y)))
+                .ok_or(format!("{} has no ship on {}", player, cell_name(x, y)))?;
+            Ok(Action::Patrol { ship, dir })
+        }
+        "PASS" => Ok(Action::EndTurn),
+        "RESIGNS" => Ok(Action::Resign),
+        _ => Err(format!("'{}' isn't a move", word(0)?)),
+    }
+}
+
+/// A ship as its first and last cell, such as `A1-A4`, or one cell for a ship that long.
+fn ends_text(positions: &[(usize, usize)]) -> String {
+    match (positions.first(), positions.last()) {
+        (Some(&(x, y)), Some(&last)) if (x, y) == last => cell_name(x, y),
+        (Some(&(x, y)), Some(&(last_x, last_y))) => format!("{}-{}", cell_name(x, y), cell_name(last_x, last_y)),
+        _ => String::new(),
+    }
+}
+
+/// Reads a ship written by `ends_text`.
+fn parse_ends(text: &str) -> Option<ShipEnds> {
+    match text.split_once('-') {
+        Some((first, last)) => Some((parse_cell(first)?, parse_cell(last)?)),
+        None => parse_cell(text).map(|cell| (cell, cell)),
+    }
+}
+
+/// A fleet without a name, such as `Battleship 4x1, Cruiser 3x2`. Display names aren't kept.
+fn fleet_text(fleet: &Fleet) -> String {
+    fleet.entries.iter()
+        .map(|entry| format!("{:?} {}x{}", entry.ship_type, entry.length, entry.count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Reads a fleet written by `fleet_text`.
+fn parse_fleet(text: &str) -> Option<Fleet> {
+    let mut fleet = Fleet::new();
+    for entry in text.split(',') {
+        let (ship_type, size) = entry.trim().split_once(' ')?;
+        let (length, count) = size.split_once('x')?;
+        fleet = fleet.with(FleetEntry::new(ShipType::from_name(ship_type)?, length.parse().ok()?, count.parse().ok()?));
+    }
+    Some(fleet)
+}
+
+/// Who played Player 2, as `human`, a level such as `hard`, or `engine:COMMAND`.
+fn seat_text(seat: &Seat) -> String {
+    match seat {
+        Seat::Human => "human".to_string(),
+        Seat::Computer(level) => level.name().to_lowercase(),
+        Seat::Engine(command) => format!("engine:{}", command),
+    }
+}
+
+/// A header value in double quotes, with any `"` or `\` in it escaped by a `\`.
+fn quoted(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Reads a header value written by `quoted`, None if it isn't in quotes.
+fn unquoted(text: &str) -> Option<String> {
+    let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next()?),
+            '"' => return None,
+            c => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+/// Reads a seat written by `seat_text`.
+fn parse_seat(text: &str) -> Option<Seat> {
+    if let Some(command) = text.strip_prefix("engine:") {
+        return Some(Seat::Engine(command.to_string()));
+    }
+    if text.eq_ignore_ascii_case("human") {
+        return Some(Seat::Human);
+    }
+    Difficulty::from_name(text).map(Seat::Computer)
+}
+
+/* ------ Tests ------ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays a Twist game between two computer players for about `actions` actions, then at the start of
+    /// Player 1's next turn they make a move and resign
+    fn recorded_game(seed: u64, actions: usize, player_two: Seat) -> (Game, Replay) {
+        let rules = Rules::new(Mode::Twist).with_fleet(Fleet::eastern_european()).with_no_touching(true);
+        let mut game = Game::with_seed(rules, seed).unwrap();
+        let mut players = [Difficulty::Hard.opponent(seed), Difficulty::Normal.opponent(seed + 1)];
+        let mut recorder = ReplayRecorder::new(&mut game, player_two);
+        game.start();
+        let mut taken = 0;
+        loop {
+            match game.phase {
+                Phase::Handoff => {
+                    game.begin_turn();
+                    if taken >= actions && game.current() == Some(PlayerId::One) {
+                        break;
+                    }
+                }
+                Phase::Turn(id) => {
+                    let action = players[if id == PlayerId::One { 0 } else { 1 }].choose_action(&game, id);
+                    game.apply(action).unwrap();
+                    recorder.record(&game, id, action);
+                    taken += 1;
+                }
+                _ => panic!("the game ended before Player 1 resigned"),
+            }
+        }
+
+        let moves = game.legal_actions(PlayerId::One).into_iter().filter(|&action| action != Action::EndTurn);
+        for action in moves.take(1).chain([Action::Resign]) {
+            game.apply(action).unwrap();
+            recorder.record(&game, PlayerId::One, action);
+        }
+        (game, recorder.replay().clone())
+    }
+
+    #[test]
+    fn written_game_reads_back_the_same() {
+        let (game, replay) = recorded_game(11, 100, Seat::Computer(Difficulty::Normal));
+        assert_eq!(game.phase, Phase::GameOver { winner: PlayerId::Two, turns: game.turn });
+        assert_eq!(replay.moves.last().map(|step| step.action), Some(Action::Resign));
+
+        let text = write(&replay).unwrap();
+        let read = read(&text).unwrap();
+        assert_eq!(read.moves, replay.moves);
+        assert_eq!(read.player_two, replay.player_two);
+        let played = read.play().unwrap();
+        assert_eq!(serde_json::to_value(played.state()).unwrap(), serde_json::to_value(game.state()).unwrap());
+    }
+
+    #[test]
+    fn header_values_are_escaped() {
+        let engine = Seat::Engine(r#"./bot --name "big \ bot""#.to_string());
+        let (_, replay) = recorded_game(12, 20, engine.clone());
+        let read = read(&write(&replay).unwrap()).unwrap();
+        assert_eq!(read.player_two, engine);
+    }
+}
@@ -0,0 +1,541 @@
+//! Random fleet placement.
+//! The old placer gave each ship 100 random tries and silently dropped it if none fitted, which could
+//! leave a game that nobody can win. This searches every layout in a random order instead, so a fleet that fits
+//! is placed and `RulesError::NoLayout` is only returned once every layout has been ruled out. A board so crowded
+//! that the search runs out of steps first gives `RulesError::GaveUp`, which doesn't claim the fleet can't fit.
+//!
+//! The no-touching rule is handled by giving every ship an extra row and column of padding below and to
+//! the right of it, on a board one row and column bigger. Two padded ships overlap exactly when the real
+//! ships would touch, so both rules become "ships can't overlap" and the same search works for each.
+//!
+//! A `Strategy` changes which spots the search tries first, so a fleet can hug the edges, spread out,
+//! bunch up or keep away from the cells a probability hunter fires at first, while still always being placed.
+//! A fleet laid out by hand (from notation or a drawn board) is checked against the same rules by `fleet_from_ends`.
+
+/* ------ Import Used Libraries ------ */
+// Random library
+use ::rand::prelude::*;
+use crate::base::{Orientation, Ship};
+use crate::error::RulesError;
+use crate::rules::Rules;
+// Saving and loading
+use serde::{Deserialize, Serialize};
+
+/*------ Constants ------ */
+const RANDOM_STEPS: usize = 2_000; // Ships tried by the ship-by-ship search before switching to packing
+const PACK_STEPS: usize = 1_000_000; // Cells the packing search tries before giving up, so a fleet that barely fits can't freeze the game
+const EDGE_WEIGHT: f64 = 10.0;     // How much more often a ship lying along the edge is tried than one in open water
+const SPREAD_POWER: i32 = 3;       // Spread out spots count the gap to the nearest ship cubed
+const CLUSTER_POWER: i32 = 3;      // Clustered spots count one over the gap to the nearest ship cubed
+const ANTI_DENSITY_POWER: i32 = 6; // Anti-density spots count how much quieter they are than the busiest cell, to the sixth power
+const HABIT_AVOID_WEIGHT: f64 = 2.0; // How much a cell the opponent always fires at first adds to how busy it counts as
+
+/*------ Enums and Structs ------ */
+/// The first and last cell of a ship laid out by hand, see `fleet_from_ends`.
+pub type ShipEnds = ((usize, usize), (usize, usize));
+
+/// How a fleet is laid out.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)] // Copy - Enables bitwise copying of the type. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}. Serialize/Deserialize - Allows saving and loading
+pub enum Strategy {
+    Random,      // Every layout as likely as any other
+    EdgeHugging, // Ships along the edges of the board
+    SpreadOut,   // Ships as far from each other as they'll go
+    Clustered,   // Ships bunched up together
+    AntiDensity, // Ships away from the cells most layouts cover, which probability hunters fire at first
+}
+
+/// Where one ship starts and which way it points.
+#[derive(Clone, Copy, PartialEq, Debug)] // Copy - Enables bitwise copying of the type. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}
+struct Anchor {
+    x: usize,          // Row of the top/left end
+    y: usize,          // Column of the top/left end
+    horizontal: bool,  // True if the ship runs along the row
+}
+
+/// State of the placement search, everything is in padded board coordinates.
+struct Search {
+    rows: usize,                    // Board rows, plus one if ships can't touch
+    cols: usize,                    // Board columns, plus one if ships can't touch
+    pad: usize,                     // 1 if ships can't touch, 0 otherwise
+    occupied: Vec<Vec<bool>>,       // Cells taken by padded ships (or left as water by the packing search)
+    free: usize,                    // Cells not yet taken
+    placed: Vec<(usize, Anchor)>,   // Length and anchor of every ship placed so far
+    steps: usize,                   // Ships (or cells, when packing) tried so far by the current search
+    strategy: Strategy,             // Which spots the ship-by-ship search tries first
+    coverage: Vec<Vec<f64>>,        // How busy each real cell is for a hunter on an empty board (or one with known habits), for anti-density
+}
+
+/* ------ Struct Implementations ------ */
+impl Strategy {
+    /// Every strategy
+    pub const ALL: [Strategy; 5] = [Strategy::Random, Strategy::EdgeHugging, Strategy::SpreadOut, Strategy::Clustered, Strategy::AntiDensity];
+
+    /// Reads a strategy name as typed on the command line, e.g. "edge".
+    pub fn from_name(name: &str) -> Option<Strategy> {
+        Strategy::ALL.into_iter().find(|strategy| strategy.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Short name used on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Strategy::Random => "random",
+            Strategy::EdgeHugging => "edge",
+            Strategy::SpreadOut => "spread",
+            Strategy::Clustered => "clustered",
+            Strategy::AntiDensity => "anti-density",
+        }
+    }
+}
+
+impl Anchor {
+    /// Every real cell a ship of `length` covers from this anchor.
+    fn cells(&self, length: usize) -> Vec<(usize, usize)> {
+        (0..length)
+            .map(|i| if self.horizontal { (self.x, self.y + i) } else { (self.x + i, self.y) })
+            .collect()
+    }
+}
+
+impl Search {
+    /// Rows and columns a padded ship takes up.
+    fn footprint(&self, length: usize, horizontal: bool) -> (usize, usize) {
+        if horizontal {
+            (1 + self.pad, length + self.pad)
+        } else {
+            (length + self.pad, 1 + self.pad)
+        }
+    }
+
+    /// Cells a padded ship of `length` takes up, used to check there is still room for the rest of the fleet.
+    fn area(&self, length: usize) -> usize {
+        (length + self.pad) * (1 + self.pad)
+    }
+
+    /// Returns true if a ship of `length` fits at `anchor` without overlapping anything.
+    fn fits(&self, anchor: Anchor, length: usize) -> bool {
+        let (h, w) = self.footprint(length, anchor.horizontal);
+        if anchor.x + h > self.rows || anchor.y + w > self.cols {
+            return false;
+        }
+        (anchor.x..anchor.x + h).all(|x| (anchor.y..anchor.y + w).all(|y| !self.occupied[x][y]))
+    }
+
+    /// Marks or clears the padded cells of a ship.
+    fn mark(&mut self, anchor: Anchor, length: usize, taken: bool) {
+        let (h, w) = self.footprint(length, anchor.horizontal);
+        for x in anchor.x..anchor.x + h {
+            for y in anchor.y..anchor.y + w {
+                self.occupied[x][y] = taken;
+            }
+        }
+        if taken {
+            self.free -= h * w;
+            self.placed.push((length, anchor));
+        } else {
+            self.free += h * w;
+            self.placed.pop();
+        }
+    }
+
+    /// Which ways round a ship of `length` can go, a single cell ship looks the same either way.
+    fn directions(length: usize) -> &'static [bool] {
+        if length == 1 { &[true] } else { &[true, false] }
+    }
+
+    /// How strongly the strategy wants a ship of `length` at `anchor`, 1 for a spot it doesn't care about.
+    fn weight(&self, anchor: Anchor, length: usize) -> f64 {
+        let cells = anchor.cells(length);
+        let (rows, cols) = (self.rows - self.pad, self.cols - self.pad);
+
+        match self.strategy {
+            Strategy::Random => 1.0,
+            Strategy::EdgeHugging => {
+                let on_edge = cells.iter().filter(|&&(x, y)| x == 0 || y == 0 || x == rows - 1 || y == cols - 1).count();
+                1.0 + EDGE_WEIGHT * on_edge as f64 / length as f64
+            }
+            Strategy::SpreadOut => match self.gap(&cells) {
+                Some(gap) => (gap as f64).powi(SPREAD_POWER),
+                None => 1.0,
+            },
+            Strategy::Clustered => match self.gap(&cells) {
+                Some(gap) => 1.0 / (gap as f64).powi(CLUSTER_POWER),
+                None => 1.0,
+            },
+            Strategy::AntiDensity => {
+                let mean = cells.iter().map(|&(x, y)| self.coverage[x][y]).sum::<f64>() / length as f64;
+                (1.0 / mean).powi(ANTI_DENSITY_POWER)
+            }
+        }
+    }
+
+    /// Smallest number of king's moves from any of `cells` to a ship already placed (1 when touching),
+    /// None if nothing has been placed yet.
+    fn gap(&self, cells: &[(usize, usize)]) -> Option<usize> {
+        self.placed.iter()
+            .flat_map(|&(length, anchor)| anchor.cells(length))
+            .flat_map(|(px, py)| cells.iter().map(move |&(x, y)| x.abs_diff(px).max(y.abs_diff(py))))
+            .min()
+    }
+
+    /// Puts `anchors` in the order to try them: a plain shuffle for random placement, otherwise a shuffle
+    /// where each anchor's chance of coming early grows with its weight.
+    fn order(&self, anchors: &mut Vec<Anchor>, length: usize, rng: &mut (impl Rng + ?Sized)) {
+        if self.strategy == Strategy::Random {
+            anchors.shuffle(rng);
+            return;
+        }
+        // Each anchor draws a key of u^(1 / weight) and the highest keys go first
+        let mut keyed: Vec<(f64, Anchor)> = anchors.iter()
+            .map(|&anchor| (rng.random::<f64>().powf(1.0 / self.weight(anchor, length)), anchor))
+            .collect();
+        keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+        *anchors = keyed.into_iter().map(|(_, anchor)| anchor).collect();
+    }
+
+    /// Ship-by-ship search: places ship `i` of `lengths` (longest first) anywhere it fits, in the order
+    /// the strategy picks, then the rest after it. Gives evenly spread layouts on roomy boards.
+    /// Returns None if it ran out of steps before finishing or ruling every layout out.
+    fn place_ships(&mut self, lengths: &[usize], i: usize, rng: &mut (impl Rng + ?Sized)) -> Option<bool> {
+        if i == lengths.len() {
+            return Some(true);
+        }
+        // Not enough water left for the rest of the fleet
+        if self.free < lengths[i..].iter().map(|&length| self.area(length)).sum::<usize>() {
+            return Some(false);
+        }
+
+        let length = lengths[i];
+        let mut anchors = Vec::new();
+        for &horizontal in Search::directions(length) {
+            for x in 0..self.rows {
+                for y in 0..self.cols {
+                    let anchor = Anchor { x, y, horizontal };
+                    if self.fits(anchor, length) {
+                        anchors.push(anchor);
+                    }
+                }
+            }
+        }
+        // Every spot is tried for every ship, same length ships included, as only trying them in one order would
+        // bias where they end up. A fleet that can't fit runs out of steps and is left to the packing search,
+        // which does treat them as interchangeable
+        self.order(&mut anchors, length, rng);
+
+        for anchor in anchors {
+            self.steps += 1;
+            if self.steps > RANDOM_STEPS {
+                return None;
+            }
+
+            self.mark(anchor, length, true);
+            match self.place_ships(lengths, i + 1, rng) {
+                Some(false) => self.mark(anchor, length, false),
+                done => return done,
+            }
+        }
+        Some(false)
+    }
+
+    /// Packing search for crowded boards: the first free cell (reading left to right, top to bottom)
+    /// either starts one of the remaining ships or is left as water, as long as enough water is left over.
+    /// Crowded boards leave little room to choose, so this ignores the strategy.
+    /// `counts` holds each distinct length and how many of it are still to place, `water` is how many
+    /// cells can still be left empty. Returns None if it ran out of `PACK_STEPS` cells before finishing.
+    fn pack(&mut self, counts: &mut Vec<(usize, usize)>, water: usize, cell: usize, rng: &mut (impl Rng + ?Sized)) -> Option<bool> {
+        if counts.iter().all(|&(_, count)| count == 0) {
+            return Some(true);
+        }
+        self.steps += 1;
+        if self.steps > PACK_STEPS {
+            return None;
+        }
+        let Some(cell) = (cell..self.rows * self.cols).find(|&c| !self.occupied[c / self.cols][c % self.cols]) else {
+            return Some(false);
+        };
+        let (x, y) = (cell / self.cols, cell % self.cols);
+
+        let mut options: Vec<Option<(usize, bool)>> = Vec::new();
+        for (group, &(length, count)) in counts.iter().enumerate() {
+            if count > 0 {
+                for &horizontal in Search::directions(length) {
+                    if self.fits(Anchor { x, y, horizontal }, length) {
+                        options.push(Some((group, horizontal)));
+                    }
+                }
+            }
+        }
+        options.shuffle(rng);
+        // Leave the cell empty about as often as cells have to be left empty, so ships don't all bunch up top
+        if water > 0 {
+            if rng.random_range(0..self.free) < water {
+                options.insert(0, None);
+            } else {
+                options.push(None);
+            }
+        }
+
+        for option in options {
+            match option {
+                None => {
+                    self.occupied[x][y] = true;
+                    self.free -= 1;
+                    match self.pack(counts, water - 1, cell + 1, rng) {
+                        Some(false) => {}
+                        done => return done,
+                    }
+                    self.occupied[x][y] = false;
+                    self.free += 1;
+                }
+                Some((group, horizontal)) => {
+                    let anchor = Anchor { x, y, horizontal };
+                    let length = counts[group].0;
+                    self.mark(anchor, length, true);
+                    counts[group].1 -= 1;
+                    match self.pack(counts, water, cell + 1, rng) {
+                        Some(false) => {}
+                        done => return done,
+                    }
+                    counts[group].1 += 1;
+                    self.mark(anchor, length, false);
+                }
+            }
+        }
+        Some(false)
+    }
+}
+
+/* ------ Functions ------ */
+/// Places the whole fleet from `rules` at random, with random orientations, favouring the spots `strategy` likes.
+/// Ships are returned in fleet order. Fails with `NoLayout` if no layout of the fleet exists on the board, or
+/// `GaveUp` if the board is so crowded that the search ran out of steps before it could tell.
+pub fn place_fleet(rules: &Rules, strategy: Strategy, rng: &mut (impl Rng + ?Sized)) -> Result<Vec<Ship>, RulesError> {
+    search_fleet(rules, strategy, coverage(rules), rng)
+}
+
+/// Places the fleet anti-density style, also keeping away from the cells an opponent tends to fire at first.
+/// `first_shots` gives each cell from 0 (never fired at early) to 1 (always), see `Habits::shot_heat`.
+/// Heat for a different size of board is ignored, leaving plain anti-density.
+pub fn place_fleet_avoiding(rules: &Rules, first_shots: &[Vec<f64>], rng: &mut (impl Rng + ?Sized)) -> Result<Vec<Ship>, RulesError> {
+    let mut busy = coverage(rules);
+    if first_shots.len() != rules.height || first_shots.iter().any(|row| row.len() != rules.width) {
+        return search_fleet(rules, Strategy::AntiDensity, busy, rng);
+    }
+    for (busy_row, shot_row) in busy.iter_mut().zip(first_shots) {
+        for (cell, shots) in busy_row.iter_mut().zip(shot_row) {
+            *cell += HABIT_AVOID_WEIGHT * shots;
+        }
+    }
+    search_fleet(rules, Strategy::AntiDensity, busy, rng)
+}
+
+/// A fleet laid out by hand, from the first and last cell of each ship in the order the rules' fleet lists them.
+/// Fails if there are too many or too few ships, or a ship is off the board, isn't a straight line of its
+/// length, overlaps another or (when ships can't touch) is next to one.
+pub fn fleet_from_ends(rules: &Rules, ends: &[ShipEnds]) -> Result<Vec<Ship>, RulesError> {
+    if ends.len() != rules.fleet.ship_count() {
+        return Err(RulesError::BadFleet(format!("the fleet has {} ships but {} were given", rules.fleet.ship_count(), ends.len())));
+    }
+
+    let entries = rules.fleet.entries.iter().flat_map(|entry| std::iter::repeat_n(entry, entry.count));
+    let mut owner = vec![vec![None; rules.width]; rules.height]; // Which ship is on each cell
+    let mut ships = Vec::new();
+    for (i, (entry, &(first, last))) in entries.zip(ends).enumerate() {
+        let name = entry.display_name();
+        let (start, end) = (first.min(last), first.max(last));
+        if end.0 >= rules.height || end.1 >= rules.width {
+            return Err(RulesError::BadFleet(format!("the {} is off the board", name)));
+        }
+        let horizontal = start.0 == end.0;
+        let span = if horizontal { end.1 - start.1 } else { end.0 - start.0 };
+        if !(horizontal || start.1 == end.1) || span + 1 != entry.length {
+            return Err(RulesError::BadFleet(format!("the {} should be a straight line {} cells long", name, entry.length)));
+        }
+
+        let anchor = Anchor { x: start.0, y: start.1, horizontal };
+        let positions = anchor.cells(entry.length);
+        for &(x, y) in &positions {
+            if owner[x][y].is_some() {
+                return Err(RulesError::BadFleet(format!("the {} overlaps another ship", name)));
+            }
+            owner[x][y] = Some(i);
+        }
+        ships.push(Ship {
+            ship_type: entry.ship_type,
+            name,
+            positions,
+            orientation: if horizontal { Orientation::Horizontal } else { Orientation::Verticle },
+        });
+    }
+
+    if rules.no_touching {
+        for (i, ship) in ships.iter().enumerate() {
+            for &(x, y) in &ship.positions {
+                let touching = (x.saturating_sub(1)..=(x + 1).min(rules.height - 1))
+                    .flat_map(|nx| (y.saturating_sub(1)..=(y + 1).min(rules.width - 1)).map(move |ny| (nx, ny)))
+                    .any(|(nx, ny)| owner[nx][ny].is_some_and(|other| other != i));
+                if touching {
+                    return Err(RulesError::BadFleet(format!("the {} touches another ship", ship.name)));
+                }
+            }
+        }
+    }
+
+    Ok(ships)
+}
+
+/// Runs the placement search with `coverage` as how busy each cell is for anti-density.
+fn search_fleet(rules: &Rules, strategy: Strategy, coverage: Vec<Vec<f64>>, rng: &mut (impl Rng + ?Sized)) -> Result<Vec<Ship>, RulesError> {
+    let pad = if rules.no_touching { 1 } else { 0 };
+    let (rows, cols) = (rules.height + pad, rules.width + pad);
+    let mut search = Search {
+        rows,
+        cols,
+        pad,
+        occupied: vec![vec![false; cols]; rows],
+        free: rows * cols,
+        placed: Vec::new(),
+        steps: 0,
+        strategy,
+        coverage,
+    };
+
+    // Longest first so the hardest ships are placed while there is the most room
+    let mut lengths: Vec<usize> = rules.fleet.entries.iter()
+        .flat_map(|entry| std::iter::repeat_n(entry.length, entry.count))
+        .collect();
+    lengths.sort_by(|a, b| b.cmp(a));
+
+    let found = match search.place_ships(&lengths, 0, rng) {
+        Some(found) => found,
+        None => {
+            // Too crowded to find a layout by picking spots at random, pack the board cell by cell instead
+            for row in search.occupied.iter_mut() {
+                row.fill(false);
+            }
+            search.free = rows * cols;
+            search.placed.clear();
+            search.steps = 0;
+
+            let mut counts: Vec<(usize, usize)> = Vec::new();
+            for &length in &lengths {
+                match counts.last_mut() {
+                    Some((last, count)) if *last == length => *count += 1,
+                    _ => counts.push((length, 1)),
+                }
+            }
+            let needed: usize = lengths.iter().map(|&length| search.area(length)).sum();
+            if needed > search.free {
+                false
+            } else {
+                match search.pack(&mut counts, search.free - needed, 0, rng) {
+                    Some(found) => found,
+                    None => return Err(RulesError::GaveUp { width: rules.width, height: rules.height, no_touching: rules.no_touching }),
+                }
+            }
+        }
+    };
+    if !found {
+        return Err(RulesError::NoLayout { width: rules.width, height: rules.height, no_touching: rules.no_touching });
+    }
+
+    // Hand the spots out to the fleet's ships, shuffled so which of a group of same length ships goes where is random too
+    let mut placed = search.placed;
+    placed.shuffle(rng);
+    let mut ships = Vec::new();
+    for entry in &rules.fleet.entries {
+        for _ in 0..entry.count {
+            let i = placed.iter().position(|&(length, _)| length == entry.length).expect("every ship was placed");
+            let (_, anchor) = placed.swap_remove(i);
+            ships.push(Ship {
+                ship_type: entry.ship_type,
+                name: entry.display_name(),
+                positions: anchor.cells(entry.length),
+                orientation: if anchor.horizontal { Orientation::Horizontal } else { Orientation::Verticle },
+            });
+        }
+    }
+
+    Ok(ships)
+}
+
+/// For every cell of an empty board, how many ways the fleet's ships could be laid over it, as a share
+/// of the busiest cell. Cells near the middle are covered the most, which is where a probability hunter starts.
+fn coverage(rules: &Rules) -> Vec<Vec<f64>> {
+    let mut coverage = vec![vec![0.0; rules.width]; rules.height];
+    for entry in &rules.fleet.entries {
+        for &horizontal in Search::directions(entry.length) {
+            for x in 0..rules.height {
+                for y in 0..rules.width {
+                    let anchor = Anchor { x, y, horizontal };
+                    let cells = anchor.cells(entry.length);
+                    if cells.iter().all(|&(cx, cy)| cx < rules.height && cy < rules.width) {
+                        for (cx, cy) in cells {
+                            coverage[cx][cy] += entry.count as f64;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Every cell is covered at least once, validation only lets through ships that fit along the longer side
+    let busiest = coverage.iter().flatten().copied().fold(1.0, f64::max);
+    for row in coverage.iter_mut() {
+        for cell in row.iter_mut() {
+            *cell /= busiest;
+        }
+    }
+    coverage
+}
+
+/* ------ Tests ------ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::ShipType;
+    use crate::rules::{Fleet, FleetEntry, Mode};
+    use ::rand::rngs::StdRng;
+
+    #[test]
+    fn impossible_fleet_is_an_error() {
+        // With their padding the ships need 34 of the 36 cells, so only the search itself can rule this out
+        let fleet = Fleet::new().with(FleetEntry::new(ShipType::Battleship, 4, 3)).with(FleetEntry::new(ShipType::Destroyer, 1, 1));
+        let rules = Rules::new(Mode::Classic).with_board(5, 5).with_fleet(fleet).with_no_touching(true);
+        for strategy in Strategy::ALL {
+            let placed = place_fleet(&rules, strategy, &mut StdRng::seed_from_u64(1));
+            assert_eq!(placed.err(), Some(RulesError::NoLayout { width: 5, height: 5, no_touching: true }));
+        }
+    }
+
+    #[test]
+    fn fleet_that_only_just_fits_is_placed() {
+        // Three ships the length of the board fit only on every other row or column
+        let fleet = Fleet::new().with(FleetEntry::new(ShipType::Dreadnaught, 5, 3));
+        let rules = Rules::new(Mode::Classic).with_board(5, 5).with_fleet(fleet).with_no_touching(true);
+        for strategy in Strategy::ALL {
+            for seed in 0..5 {
+                let ships = place_fleet(&rules, strategy, &mut StdRng::seed_from_u64(seed));
+                assert_eq!(ships.map(|ships| ships.len()).ok(), Some(3), "{} with seed {}", strategy.name(), seed);
+            }
+        }
+    }
+
+    #[test]
+    fn no_touching_keeps_ships_apart() {
+        let rules = Rules::new(Mode::Classic).with_fleet(Fleet::eastern_european()).with_no_touching(true);
+        for strategy in Strategy::ALL {
+            for seed in 0..5 {
+                let ships = place_fleet(&rules, strategy, &mut StdRng::seed_from_u64(seed)).unwrap();
+                assert_eq!(ships.len(), rules.fleet.ship_count());
+                for (i, ship) in ships.iter().enumerate() {
+                    for other in &ships[i + 1..] {
+                        let touching = ship.positions.iter().any(|&(x, y)| {
+                            other.positions.iter().any(|&(ox, oy)| x.abs_diff(ox) <= 1 && y.abs_diff(oy) <= 1)
+                        });
+                        assert!(!touching, "{} ships touch with seed {}", strategy.name(), seed);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! What the computer remembers about each human between games.
+//! People tend to lay their fleets out the same way and open with the same shots, so at the end of every
+//! finished game against the computer the human's final ship cells and first few shots are added to a heatmap
+//! under their profile name. Before the next game the computer fires at where their ships usually are first
+//! (see `Opponent::study`), and Expert and Master lay their own fleet out away from where they usually fire first.
+//! Every profile lives in one JSON file, with a separate pair of heatmaps for each board size played.
+
+/* ------ Import Used Libraries ------ */
+use crate::ai::{Difficulty, Opponent};
+use crate::base::*;
+use crate::error::{FileError, RulesError};
+use crate::events::GameEvent;
+use crate::game::{Game, Phase};
+use crate::placement::{place_fleet_avoiding, Strategy};
+use crate::save::{read_json, write_json};
+use crate::twist::ActionType;
+// Random library
+use ::rand::SeedableRng;
+use ::rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::mpsc::Receiver;
+
+/*------ Constants ------ */
+pub const PROFILE_FILE: &str = "profiles.json"; // Where profiles are kept, in the folder the game is run from
+pub const DEFAULT_PROFILE: &str = "player";     // Profile used when no name is given
+const FIRST_SHOTS: usize = 10;                  // Shots a game that count as where someone fires first
+const SMOOTHING_GAMES: f64 = 3.0;               // Imaginary games with nothing in them, so one game doesn't count as a habit
+const LAYOUT_SALT: u64 = 0xA5A5_5A5A_C3C3_3C3C; // Mixed into the game's seed so the computer's layout doesn't follow the deal
+
+/*------ Enums and Structs ------ */
+/// Every profile in the profile file.
+#[derive(Clone, Default, Debug, Serialize, Deserialize)] // Default - No profiles yet. Serialize/Deserialize - Allows saving and loading
+pub struct Profiles {
+    pub players: Vec<Profile>, // One per name, in the order they were first used
+}
+
+/// Everything remembered about one person.
+#[derive(Clone, Debug, Serialize, Deserialize)] // Clone - Creates a deep copy of the value. Serialize/Deserialize - Allows saving and loading
+pub struct Profile {
+    pub name: String,        // Picked with --profile
+    pub boards: Vec<Habits>, // One for each board size they have finished a game on
+}
+
+/// How someone has played on one size of board.
+#[derive(Clone, Debug, Serialize, Deserialize)] // Clone - Creates a deep copy of the value. Serialize/Deserialize - Allows saving and loading
+pub struct Habits {
+    pub width: usize,               // Board width in cells
+    pub height: usize,              // Board height in cells
+    pub games: u32,                 // Finished games recorded
+    pub ships: Vec<Vec<u32>>,       // Games that ended with one of their ships on each cell
+    pub first_shots: Vec<Vec<u32>>, // Games where each cell was one of their first `FIRST_SHOTS` shots
+}
+
+/// Watches a game for one player's opening shots, so they can be added to their habits once it's over.
+pub struct HabitRecorder {
+    player: PlayerId,                 // Who is being watched
+    events: Receiver<GameEvent>,      // The game's feedback
+    first_shots: Vec<(usize, usize)>, // Cells they fired missiles at, in order
+}
+
+/* ------ Struct Implementations ------ */
+impl Profiles {
+    /// Reads the profiles from `path`, no file yet just means no profiles yet.
+    /// Fails if a heatmap isn't the size of the board it's listed under, as from a file edited by hand.
+    pub fn load(path: &str) -> Result<Profiles, FileError> {
+        if !Path::new(path).exists() {
+            return Ok(Profiles::default());
+        }
+        let profiles: Profiles = read_json(path)?;
+        for profile in &profiles.players {
+            if let Some(habits) = profile.boards.iter().find(|habits| !habits.is_sized()) {
+                let reason = format!("{}'s heatmaps for the {}x{} board aren't {} columns by {} rows", profile.name, habits.width, habits.height, habits.width, habits.height);
+                return Err(FileError::Format { path: path.to_string(), reason });
+            }
+        }
+        Ok(profiles)
+    }
+
+    /// Writes every profile to `path`, replacing what was there.
+    pub fn save(&self, path: &str) -> Result<(), FileError> {
+        write_json(path, self)
+    }
+
+    /// `name`'s habits on a board `width` by `height`, None if they haven't finished a game on one.
+    pub fn habits(&self, name: &str, width: usize, height: usize) -> Option<&Habits> {
+        self.players.iter()
+            .find(|profile| profile.name == name)?
+            .boards.iter()
+            .find(|habits| habits.width == width && habits.height == height)
+    }
+
+    /// `name`'s habits on a board `width` by `height`, starting the profile or the board size if they're new.
+    pub fn habits_mut(&mut self, name: &str, width: usize, height: usize) -> &mut Habits {
+        let profile = match self.players.iter().position(|profile| profile.name == name) {
+            Some(i) => &mut self.players[i],
+            None => {
+                self.players.push(Profile { name: name.to_string(), boards: Vec::new() });
+                self.players.last_mut().expect("just added")
+            }
+        };
+        match profile.boards.iter().position(|habits| habits.width == width && habits.height == height) {
+            Some(i) => &mut profile.boards[i],
+            None => {
+                profile.boards.push(Habits::new(width, height));
+                profile.boards.last_mut().expect("just added")
+            }
+        }
+    }
+}
+
+impl Habits {
+    /// Nothing recorded yet on a board `width` columns by `height` rows.
+    pub fn new(width: usize, height: usize) -> Self {
+        Habits {
+            width,
+            height,
+            games: 0,
+            ships: vec![vec![0; width]; height],
+            first_shots: vec![vec![0; width]; height],
+        }
+    }
+
+    /// Returns true if both heatmaps have a count for every cell of a board `width` by `height`.
+    pub fn is_sized(&self) -> bool {
+        [&self.ships, &self.first_shots].into_iter()
+            .all(|counts| counts.len() == self.height && counts.iter().all(|row| row.len() == self.width))
+    }
+
+    /// From 0 to 1 for each cell, how often a game ended with one of their ships there.
+    pub fn ship_heat(&self) -> Vec<Vec<f64>> {
+        self.heat(&self.ships)
+    }
+
+    /// From 0 to 1 for each cell, how often it was one of the first cells they fired at.
+    pub fn shot_heat(&self) -> Vec<Vec<f64>> {
+        self.heat(&self.first_shots)
+    }
+
+    /// Share of games for each count, pulled towards 0 while there are only a few games to go on.
+    fn heat(&self, counts: &[Vec<u32>]) -> Vec<Vec<f64>> {
+        let games = self.games as f64 + SMOOTHING_GAMES;
+        counts.iter().map(|row| row.iter().map(|&count| count as f64 / games).collect()).collect()
+    }
+
+    /// Adds one finished game: where their ships ended up and the cells they fired at first.
+    pub fn record(&mut self, ships: &[Ship], first_shots: &[(usize, usize)]) {
+        self.games += 1;
+        for ship in ships {
+            for &(x, y) in &ship.positions {
+                self.ships[x][y] += 1;
+            }
+        }
+        for &(x, y) in first_shots.iter().take(FIRST_SHOTS) {
+            self.first_shots[x][y] += 1;
+        }
+    }
+
+    /// Readies the computer at `level` in seat `id` for a game against someone with these habits: `opponent`
+    /// is told where their ships tend to be and, for levels that adapt their layout, its fleet is laid out
+    /// anti-density style away from where they fire first (and recorded as such in `game.layouts`).
+    /// Call before `Game::start`, the same game and habits always give the same layout.
+    pub fn prepare(&self, game: &mut Game, id: PlayerId, level: Difficulty, opponent: &mut dyn Opponent) -> Result<(), RulesError> {
+        if game.rules.width != self.width || game.rules.height != self.height {
+            return Ok(());
+        }
+
+        if level.adapts_layout() {
+            let mut rng = StdRng::seed_from_u64(game.seed ^ LAYOUT_SALT);
+            let ships = place_fleet_avoiding(&game.rules, &self.shot_heat(), &mut rng)?;
+            game.set_fleet(id, ships);
+            game.layouts[if id == PlayerId::One { 0 } else { 1 }] = Strategy::AntiDensity;
+        }
+        opponent.study(&self.ship_heat());
+        Ok(())
+    }
+}
+
+impl HabitRecorder {
+    /// Starts watching `player`'s shots in `game`.
+    pub fn new(game: &mut Game, player: PlayerId) -> Self {
+        HabitRecorder {
+            player,
+            events: game.events.subscribe(),
+            first_shots: Vec::new(),
+        }
+    }
+
+    /// Adds the game to `habits` if it was played to the end, an abandoned game says little about anyone.
+    pub fn finish(mut self, game: &Game, habits: &mut Habits) {
+        for event in self.events.try_iter() {
+            if let GameEvent::ShotResolved { player, weapon: ActionType::Missile, shot } = event {
+                if player == self.player {
+                    self.first_shots.extend(shot.cells.last());
+                }
+            }
+        }
+
+        if matches!(game.phase, Phase::GameOver { .. }) {
+            habits.record(&game.player(self.player).base.ships, &self.first_shots);
+        }
+    }
+}
+
+/* ------ Tests ------ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::rules::{Mode, Rules};
+
+    /// A file in the temporary folder for one test, removed first in case an earlier run left it behind
+    fn temp_file(test: &str) -> String {
+        let path = std::env::temp_dir().join(format!("battleships-{}-{}.json", test, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path.display().to_string()
+    }
+
+    #[test]
+    fn no_file_is_no_profiles() {
+        let profiles = Profiles::load(&temp_file("no-profiles")).unwrap();
+        assert!(profiles.players.is_empty());
+    }
+
+    #[test]
+    fn profiles_read_back_the_same() {
+        let path = temp_file("profiles");
+        let mut profiles = Profiles::default();
+        let ship = Ship { ship_type: ShipType::Destroyer, name: "Destroyer".to_string(), positions: vec![(0, 0), (0, 1)], orientation: Orientation::Horizontal };
+        profiles.habits_mut("ann", 10, 10).record(&[ship], &[(4, 4), (5, 5)]);
+        profiles.habits_mut("ann", 8, 6);
+        profiles.save(&path).unwrap();
+
+        let read = Profiles::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let habits = read.habits("ann", 10, 10).unwrap();
+        assert_eq!(habits.games, 1);
+        assert_eq!((habits.ships[0][1], habits.first_shots[5][5], habits.first_shots[0][0]), (1, 1, 0));
+        assert!(read.habits("ann", 8, 6).is_some_and(|habits| habits.games == 0));
+        assert!(read.habits("bob", 10, 10).is_none());
+    }
+
+    #[test]
+    fn heatmaps_the_wrong_size_are_refused() {
+        for (rows, columns) in [(9, 10), (10, 9)] {
+            let path = temp_file("bad-profiles");
+            let mut profiles = Profiles::default();
+            let habits = profiles.habits_mut("ann", 10, 10);
+            habits.first_shots = vec![vec![0; columns]; rows];
+            profiles.save(&path).unwrap();
+
+            let loaded = Profiles::load(&path);
+            std::fs::remove_file(&path).unwrap();
+            assert!(matches!(loaded, Err(FileError::Format { .. })), "{} rows of {} weren't refused", rows, columns);
+        }
+    }
+
+    #[test]
+    fn only_levels_that_adapt_lay_out_around_habits() {
+        let mut habits = Habits::new(10, 10);
+        habits.first_shots[4][4] = 5;
+        for level in [Difficulty::Hard, Difficulty::Expert, Difficulty::Master] {
+            let layout = level.layout(1);
+            let mut game = Game::with_layouts(Rules::new(Mode::Classic), 1, [Strategy::Random, layout]).unwrap();
+            let before: Vec<_> = game.player(PlayerId::Two).base.ships.iter().map(|ship| ship.positions.clone()).collect();
+            habits.prepare(&mut game, PlayerId::Two, level, level.opponent(1).as_mut()).unwrap();
+            let after: Vec<_> = game.player(PlayerId::Two).base.ships.iter().map(|ship| ship.positions.clone()).collect();
+
+            if level.adapts_layout() {
+                assert_ne!(before, after, "{} kept its fleet", level.name());
+                assert_eq!(game.layouts[1], Strategy::AntiDensity);
+            } else {
+                assert_eq!(before, after, "{} moved its fleet", level.name());
+                assert_eq!(game.layouts[1], layout);
+            }
+        }
+    }
+}
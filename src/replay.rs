@@ -0,0 +1,237 @@
+//! Recording a whole match so it can be played back.
+//! A replay is the position the game started from (the rules, the seed, both fleets and decks, and the seed of the
+//! random stream it carried on with) followed by every action in the order it was taken, with the cards each one
+//! drew. The rules are deterministic, so applying the actions to the starting position through `Game::apply` gives
+//! back exactly the same game, and the recorded draws are checked along the way to catch a replay recorded
+//! under different rules. `viewer.rs` in the window app steps through a replay with a caption for every move.
+
+/* ------ Import Used Libraries ------ */
+use crate::action::{cell_name, Action};
+use crate::base::{PlayerId, ShotOutcome};
+use crate::error::{FileError, ReplayError};
+use crate::events::GameEvent;
+use crate::game::{Game, GameState, Phase};
+use crate::notation;
+use crate::save::{read_json, write_json, Seat};
+use crate::twist::ActionType;
+// Saving and loading
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::Receiver;
+
+/*------ Constants ------ */
+pub const REPLAY_FILE: &str = "replay.json"; // Where a replay is written unless another file is given
+const REPLAY_VERSION: u32 = 1;               // Goes up whenever the format changes, so an old file is refused instead of misread
+
+/*------ Enums and Structs ------ */
+/// A recorded match.
+#[derive(Clone, Serialize, Deserialize)] // Clone - Creates a deep copy of the value. Serialize/Deserialize - Allows saving and loading
+pub struct Replay {
+    pub version: u32,     // Format of the file, `REPLAY_VERSION` when written by this build
+    pub player_two: Seat, // Who played Player 2, for whoever watches it
+    pub start: GameState, // The game before the first move
+    pub moves: Vec<Move>, // Every action taken, in order
+}
+
+/// One action in a replay.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)] // Clone - Creates a deep copy of the value. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}. Serialize/Deserialize - Allows saving and loading
+pub struct Move {
+    pub turn: u32,              // Round it was taken in
+    pub player: PlayerId,       // Who took it
+    pub action: Action,         // What they did
+    pub drawn: Vec<ActionType>, // Cards they drew because of it, in order (only ending a turn draws, Twist only)
+}
+
+/// Writes down each action of a game as it is taken.
+pub struct ReplayRecorder {
+    replay: Replay,              // Recorded so far
+    events: Receiver<GameEvent>, // The game's feedback, for the cards drawn
+}
+
+/* ------ Struct Implementations ------ */
+impl Replay {
+    /// Writes the replay to `path`, replacing what was there. A path ending in `.txt` gets notation, see `notation.rs`.
+    pub fn save(&self, path: &str) -> Result<(), FileError> {
+        if notation::is_notation(path) {
+            return notation::save(self, path);
+        }
+        write_json(path, self)
+    }
+
+    /// Reads a replay written by `save`.
+    pub fn load(path: &str) -> Result<Replay, FileError> {
+        if notation::is_notation(path) {
+            return notation::load(path);
+        }
+        let replay: Replay = read_json(path)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(FileError::Format { path: path.to_string(), reason: format!("recorded by version {} of the format, this build reads {}", replay.version, REPLAY_VERSION) });
+        }
+        Ok(replay)
+    }
+
+    /// The game as it was after the first `count` moves (all of them if there are fewer), played out again
+    /// from the start. Fails if a move isn't allowed or draws different cards, which means the rules have
+    /// changed since it was recorded.
+    pub fn game_after(&self, count: usize) -> Result<Game, ReplayError> {
+        self.play_through(count, |_, _, _| {})
+    }
+
+    /// The game as it was at the end of the recording.
+    pub fn play(&self) -> Result<Game, ReplayError> {
+        self.game_after(self.moves.len())
+    }
+
+    /// One line for each move saying what happened, see `caption`.
+    pub fn captions(&self) -> Result<Vec<String>, ReplayError> {
+        let mut captions = Vec::with_capacity(self.moves.len());
+        self.play_through(self.moves.len(), |step, events, _| captions.push(caption(step, events)))?;
+        Ok(captions)
+    }
+
+    /// Plays the first `count` moves from the start, handing each move, the events it caused and the game after it to `on_move`.
+    pub(crate) fn play_through(&self, count: usize, mut on_move: impl FnMut(&Move, &[GameEvent], &Game)) -> Result<Game, ReplayError> {
+        let mut game = Game::from_state(self.start.clone()).map_err(ReplayError::Start)?;
+        let feed = game.events.subscribe();
+        game.start();
+
+        for (i, step) in self.moves.iter().take(count).enumerate() {
+            // The handoff screen isn't an action, whoever moves next has already been passed the device
+            if game.phase == Phase::Handoff {
+                game.begin_turn();
+            }
+            game.apply(step.action).map_err(|error| ReplayError::Refused { index: i + 1, action: step.action, error })?;
+
+            let events: Vec<GameEvent> = feed.try_iter().collect();
+            if cards_drawn(&events) != step.drawn {
+                return Err(ReplayError::Diverged { index: i + 1 });
+            }
+            on_move(step, &events, &game);
+        }
+        Ok(game)
+    }
+}
+
+impl ReplayRecorder {
+    /// Starts recording `game` from where it is now, call before `Game::start` to record it from the beginning.
+    pub fn new(game: &mut Game, player_two: Seat) -> Self {
+        let replay = Replay { version: REPLAY_VERSION, player_two, start: game.state(), moves: Vec::new() };
+        ReplayRecorder::resume(game, replay)
+    }
+
+    /// Carries on recording `game` into a replay recorded up to the position it is in now,
+    /// such as one kept in a save file.
+    pub fn resume(game: &mut Game, replay: Replay) -> Self {
+        ReplayRecorder { replay, events: game.events.subscribe() }
+    }
+
+    /// Writes down an action `player` has just taken, call it once `Game::apply` has succeeded.
+    pub fn record(&mut self, game: &Game, player: PlayerId, action: Action) {
+        let events: Vec<GameEvent> = self.events.try_iter().collect();
+        self.replay.moves.push(Move { turn: game.turn, player, action, drawn: cards_drawn(&events) });
+    }
+
+    /// The replay so far
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+}
+
+/* ------ Functions ------ */
+/// Describes a move from the events it caused, e.g. "P2 Torpedo column 5: stripped reinforcement at J5".
+/// Cells and columns are named the same way as in notation (see `cell_name`).
+/// Cards are named too, a replay is watched with both hands showing.
+pub fn caption(step: &Move, events: &[GameEvent]) -> String {
+    let player = step.player.short_name();
+    let mut caption = match step.action {
+        Action::Missile { x, y } => format!("{} Missile at {}", player, cell_name(x, y)),
+        Action::Torpedo { col } => format!("{} Torpedo column {}", player, col + 1),
+        Action::RadarScan { x, y } => format!("{} Radar scan at {}", player, cell_name(x, y)),
+        Action::Reinforce { x, y } => format!("{} Reinforced {}", player, cell_name(x, y)),
+        Action::Patrol { dir, .. } => format!("{} Patrol {:?}", player, dir),
+        Action::EndTurn => format!("{} ended their turn", player),
+        Action::Resign => format!("{} resigned", player),
+    };
+
+    let mut details = Vec::new();
+    for event in events {
+        match event {
+            GameEvent::ShotResolved { shot, .. } => {
+                let (x, y) = shot.cells.last().copied().unwrap_or((0, 0));
+                details.push(match shot.outcome {
+                    ShotOutcome::Miss => "miss".to_string(),
+                    // A sinking has its own event with the ship's name
+                    ShotOutcome::Hit | ShotOutcome::Sunk(_) => format!("hit at {}", cell_name(x, y)),
+                    ShotOutcome::ReinforcementStripped => format!("stripped reinforcement at {}", cell_name(x, y)),
+                    ShotOutcome::AlreadyHit => format!("stopped on an old hit at {}", cell_name(x, y)),
+                });
+            }
+            GameEvent::ShipSunk { name, .. } => details.push(format!("sank the {}", name)),
+            GameEvent::PatrolMoved { name, .. } => details.push(format!("moved the {}", name)),
+            GameEvent::CardDrawn { card, .. } => details.push(format!("drew {:?}", card)),
+            GameEvent::GameWon { winner, turns } => details.push(format!("{} wins after {} turns", winner.short_name(), turns)),
+            _ => {}
+        }
+    }
+
+    if !details.is_empty() {
+        caption.push_str(": ");
+        caption.push_str(&details.join(", "));
+    }
+    caption
+}
+
+/// Cards drawn among `events`, in order.
+fn cards_drawn(events: &[GameEvent]) -> Vec<ActionType> {
+    events.iter()
+        .filter_map(|event| match event {
+            GameEvent::CardDrawn { card, .. } => Some(*card),
+            _ => None,
+        })
+        .collect()
+}
+
+/* ------ Tests ------ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::rules::{Mode, Rules};
+
+    /// A Twist game between two computer players recorded from the start, with the game as it ended
+    fn recorded_game(seed: u64) -> (Game, Replay) {
+        let mut game = Game::with_seed(Rules::new(Mode::Twist), seed).unwrap();
+        let mut players = [Difficulty::Hard.opponent(seed), Difficulty::Normal.opponent(seed + 1)];
+        let mut recorder = ReplayRecorder::new(&mut game, Seat::Computer(Difficulty::Normal));
+        game.start();
+        for _ in 0..5_000 {
+            match game.phase {
+                Phase::Handoff => game.begin_turn(),
+                Phase::Turn(id) => {
+                    let action = players[if id == PlayerId::One { 0 } else { 1 }].choose_action(&game, id);
+                    game.apply(action).unwrap();
+                    recorder.record(&game, id, action);
+                }
+                _ => break,
+            }
+        }
+        (game, recorder.replay().clone())
+    }
+
+    #[test]
+    fn replay_plays_back_to_the_same_game() {
+        let (game, replay) = recorded_game(3);
+        assert!(matches!(game.phase, Phase::GameOver { .. }));
+
+        let text = serde_json::to_string(&replay).unwrap();
+        let read: Replay = serde_json::from_str(&text).unwrap();
+        assert_eq!(read.moves, replay.moves);
+        let played = read.play().unwrap();
+        assert_eq!(serde_json::to_value(played.state()).unwrap(), serde_json::to_value(game.state()).unwrap());
+    }
+
+    #[test]
+    fn captions_every_move() {
+        let (_, replay) = recorded_game(4);
+        assert_eq!(replay.captions().unwrap().len(), replay.moves.len());
+    }
+}
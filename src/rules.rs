@@ -0,0 +1,184 @@
+//! Settings chosen before a game starts.
+//! `Rules` is handed to `Game::new` and decides which mode is played, how big the boards are
+//! and which ships each player gets. Everything in `base.rs` and `twist.rs` reads the size from the
+//! board and the ships from the fleet instead of constants, so house rules are just a different `Rules`.
+
+/* ------ Import Used Libraries ------ */
+use crate::base::{ShipType, GRID_SIZE};
+use crate::error::RulesError;
+// Saving and loading
+use serde::{Deserialize, Serialize};
+
+/*------ Constants ------ */
+pub const MIN_BOARD_SIZE: usize = 5;  // Smallest board edge
+pub const MAX_BOARD_SIZE: usize = 26; // Largest board edge, one row per letter of the alphabet
+
+/*------ Enums and Structs ------ */
+/// Which rules are being played.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)] // Copy - Enables bitwise copying of the type. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}. Serialize/Deserialize - Allows saving and loading
+pub enum Mode {
+    Classic, // Every turn is a missile
+    Twist,   // Every turn is a card from the hand
+}
+
+/// One kind of ship in a fleet.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)] // Clone - Creates a deep copy of the value. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}. Serialize/Deserialize - Allows saving and loading
+pub struct FleetEntry {
+    pub ship_type: ShipType,  // Type the ships are reported as
+    pub length: usize,        // Cells each ship takes up
+    pub count: usize,         // How many of this ship each player gets
+    pub name: Option<String>, // Display name, the ship type is shown if there isn't one
+}
+
+/// The ships each player places at the start of the game.
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)] // Clone - Creates a deep copy of the value. Default - A fleet with no ships. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}. Serialize/Deserialize - Allows saving and loading
+pub struct Fleet {
+    pub entries: Vec<FleetEntry>, // Placed in this order
+}
+
+/// Everything about a game that is decided before it starts.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)] // Clone - Creates a deep copy of the value. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}. Serialize/Deserialize - Allows saving and loading
+pub struct Rules {
+    pub mode: Mode,    // Classic or Twist rules
+    pub width: usize,  // Number of columns on each board
+    pub height: usize, // Number of rows on each board
+    pub fleet: Fleet,  // Ships each player gets
+    pub no_touching: bool, // Ships can't be placed next to each other, not even diagonally
+}
+
+/* ------ Struct Implementations ------ */
+impl FleetEntry {
+    /// `count` ships of `length` cells, shown by their ship type.
+    pub fn new(ship_type: ShipType, length: usize, count: usize) -> Self {
+        FleetEntry {
+            ship_type,
+            length,
+            count,
+            name: None,
+        }
+    }
+
+    /// Gives the ships a display name, e.g. a Dreadnaught shown as "Carrier".
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Name shown to players
+    pub fn display_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("{:?}", self.ship_type),
+        }
+    }
+}
+
+impl Fleet {
+    /// A fleet with no ships, add to it with `with`.
+    pub fn new() -> Self {
+        Fleet { entries: Vec::new() }
+    }
+
+    /// Adds an entry to the fleet.
+    pub fn with(mut self, entry: FleetEntry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    /// The Milton Bradley fleet this game has always used, one each of 5, 4, 3, 3 and 2.
+    pub fn classic() -> Self {
+        Fleet::new()
+            .with(FleetEntry::new(ShipType::Battleship, 4, 1))
+            .with(FleetEntry::new(ShipType::Submarine, 3, 1))
+            .with(FleetEntry::new(ShipType::Cruiser, 3, 1))
+            .with(FleetEntry::new(ShipType::Dreadnaught, 5, 1))
+            .with(FleetEntry::new(ShipType::Destroyer, 2, 1))
+    }
+
+    /// The Eastern-European fleet, one ship of 4, two of 3, three of 2 and four of 1.
+    pub fn eastern_european() -> Self {
+        Fleet::new()
+            .with(FleetEntry::new(ShipType::Battleship, 4, 1))
+            .with(FleetEntry::new(ShipType::Cruiser, 3, 2))
+            .with(FleetEntry::new(ShipType::Destroyer, 2, 3))
+            .with(FleetEntry::new(ShipType::Submarine, 1, 4))
+    }
+
+    /// Reads a fleet name as typed on the command line, "classic" or "eastern".
+    pub fn from_name(name: &str) -> Option<Fleet> {
+        match name.to_ascii_lowercase().as_str() {
+            "classic" => Some(Fleet::classic()),
+            "eastern" => Some(Fleet::eastern_european()),
+            _ => None,
+        }
+    }
+
+    /// The name `from_name` reads back into this fleet, None for any other fleet
+    pub fn name(&self) -> Option<&'static str> {
+        ["classic", "eastern"].into_iter().find(|&name| Fleet::from_name(name).as_ref() == Some(self))
+    }
+
+    /// Total number of ships each player gets
+    pub fn ship_count(&self) -> usize {
+        self.entries.iter().map(|entry| entry.count).sum()
+    }
+
+    /// Total number of cells the ships take up
+    pub fn total_cells(&self) -> usize {
+        self.entries.iter().map(|entry| entry.length * entry.count).sum()
+    }
+}
+
+impl Rules {
+    /// Standard rules for `mode` on the usual 10x10 board with the classic fleet.
+    pub fn new(mode: Mode) -> Self {
+        Rules {
+            mode,
+            width: GRID_SIZE,
+            height: GRID_SIZE,
+            fleet: Fleet::classic(),
+            no_touching: false,
+        }
+    }
+
+    /// Changes the board to `width` columns by `height` rows.
+    pub fn with_board(mut self, width: usize, height: usize) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Replaces the fleet each player gets.
+    pub fn with_fleet(mut self, fleet: Fleet) -> Self {
+        self.fleet = fleet;
+        self
+    }
+
+    /// Turns the rule that ships can't touch on or off.
+    pub fn with_no_touching(mut self, no_touching: bool) -> Self {
+        self.no_touching = no_touching;
+        self
+    }
+
+    /// Checks the settings describe a game that can be played.
+    /// A fleet that passes may still have no layout, placement reports that when it runs.
+    pub fn validate(&self) -> Result<(), RulesError> {
+        let range = MIN_BOARD_SIZE..=MAX_BOARD_SIZE;
+        if !range.contains(&self.width) || !range.contains(&self.height) {
+            return Err(RulesError::BoardSize { width: self.width, height: self.height });
+        }
+
+        if self.fleet.ship_count() == 0 {
+            return Err(RulesError::EmptyFleet);
+        }
+        for entry in &self.fleet.entries {
+            if entry.length == 0 || entry.length > self.width.max(self.height) {
+                return Err(RulesError::ShipDoesNotFit { name: entry.display_name(), length: entry.length });
+            }
+        }
+        if self.fleet.total_cells() > self.width * self.height {
+            return Err(RulesError::FleetTooLarge { cells: self.fleet.total_cells(), board_cells: self.width * self.height });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+//! Saving a game in progress and picking it up again later.
+//! A save file holds the `GameState` from `game.rs` (both players' boards, guess boards and ships, whose turn it is,
+//! and in Twist the decks, hands and any patrol under way) along with who was playing Player 2, written out as JSON.
+//! A game being recorded keeps its replay in the save too, so the recording carries on once it is resumed.
+//! The window's grids aren't saved, they are recoloured from the boards every frame anyway.
+
+/* ------ Import Used Libraries ------ */
+use crate::ai::Difficulty;
+use crate::error::{FileError, RulesError};
+use crate::game::{Game, GameState};
+use crate::replay::Replay;
+// Saving and loading
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/*------ Constants ------ */
+pub const SAVE_FILE: &str = "savegame.json"; // Where a game is saved unless another file is given
+const SAVE_VERSION: u32 = 1;                 // Goes up whenever the format changes, so an old file is refused instead of misread
+
+/*------ Enums and Structs ------ */
+/// Who plays as Player 2
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)] // Clone - Creates a deep copy of the value. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}. Serialize/Deserialize - Allows saving and loading
+pub enum Seat {
+    Human,                // A second person on the same device
+    Computer(Difficulty), // The computer at this level
+    Engine(String),       // An external engine started with this command
+}
+
+/// A game saved part way through.
+#[derive(Clone, Serialize, Deserialize)] // Clone - Creates a deep copy of the value. Serialize/Deserialize - Allows saving and loading
+pub struct SavedGame {
+    pub version: u32,     // Format of the file, `SAVE_VERSION` when written by this build
+    pub player_two: Seat, // Who was playing Player 2
+    pub game: GameState,  // The game itself
+    #[serde(default)]
+    pub replay: Option<Replay>, // The game so far, if it was being recorded
+}
+
+/* ------ Struct Implementations ------ */
+impl SavedGame {
+    /// Captures `game` as it stands, see `Game::state`, along with its replay so far if it is being recorded.
+    pub fn new(game: &Game, player_two: Seat, replay: Option<Replay>) -> Self {
+        SavedGame { version: SAVE_VERSION, player_two, game: game.state(), replay }
+    }
+
+    /// Writes the game to `path`, replacing what was there.
+    pub fn save(&self, path: &str) -> Result<(), FileError> {
+        write_json(path, self)
+    }
+
+    /// Reads a game saved by `save`.
+    pub fn load(path: &str) -> Result<SavedGame, FileError> {
+        let saved: SavedGame = read_json(path)?;
+        if saved.version != SAVE_VERSION {
+            return Err(FileError::Format { path: path.to_string(), reason: format!("saved by version {} of the format, this build reads {}", saved.version, SAVE_VERSION) });
+        }
+        Ok(saved)
+    }
+
+    /// The game ready to carry on from where it was saved, who plays Player 2 in it and its replay so far.
+    pub fn resume(self) -> Result<(Game, Seat, Option<Replay>), RulesError> {
+        Ok((Game::from_state(self.game)?, self.player_two, self.replay))
+    }
+}
+
+/* ------ Functions ------ */
+/// Reads a value written by `write_json` from `path`.
+pub fn read_json<T: DeserializeOwned>(path: &str) -> Result<T, FileError> {
+    let text = std::fs::read_to_string(path).map_err(|e| FileError::Read { path: path.to_string(), reason: e.to_string() })?;
+    serde_json::from_str(&text).map_err(|e| FileError::Format { path: path.to_string(), reason: e.to_string() })
+}
+
+/// Writes `value` to `path` as JSON, replacing what was there.
+pub fn write_json<T: Serialize>(path: &str, value: &T) -> Result<(), FileError> {
+    let text = serde_json::to_string(value).map_err(|e| FileError::Write { path: path.to_string(), reason: e.to_string() })?;
+    std::fs::write(path, text).map_err(|e| FileError::Write { path: path.to_string(), reason: e.to_string() })
+}
+
+/* ------ Tests ------ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::PlayerId;
+    use crate::game::Phase;
+    use crate::rules::{Mode, Rules};
+
+    #[test]
+    fn same_seed_deals_the_same_game() {
+        let rules = Rules::new(Mode::Twist);
+        let first = Game::with_seed(rules.clone(), 21).unwrap();
+        let second = Game::with_seed(rules, 21).unwrap();
+        assert_eq!(serde_json::to_value(first.state()).unwrap(), serde_json::to_value(second.state()).unwrap());
+    }
+
+    #[test]
+    fn resumed_game_carries_on_the_random_stream() {
+        let rules = Rules::new(Mode::Twist);
+        let mut game = Game::with_seed(rules.clone(), 22).unwrap();
+        let mut players = [Difficulty::Easy.opponent(1), Difficulty::Easy.opponent(2)];
+        let mut play = |games: &mut [&mut Game], steps: usize| {
+            for _ in 0..steps {
+                match games[0].phase {
+                    Phase::Handoff => games.iter_mut().for_each(|game| game.begin_turn()),
+                    Phase::Turn(id) => {
+                        let action = players[if id == PlayerId::One { 0 } else { 1 }].choose_action(games[0], id);
+                        for game in games.iter_mut() {
+                            game.apply(action).unwrap();
+                        }
+                    }
+                    _ => break,
+                }
+            }
+        };
+        game.start();
+        play(&mut [&mut game], 400);
+        // Random shots take long enough that the decks run out and are shuffled again
+        let dealt = Game::with_seed(rules, 22).unwrap();
+        assert!(game.state().rng_words > dealt.state().rng_words);
+        assert!(!matches!(game.phase, Phase::GameOver { .. }));
+
+        let text = serde_json::to_string(&SavedGame::new(&game, Seat::Human, None)).unwrap();
+        let saved: SavedGame = serde_json::from_str(&text).unwrap();
+        let (mut resumed, seat, replay) = saved.resume().unwrap();
+        assert_eq!(seat, Seat::Human);
+        assert!(replay.is_none());
+
+        // Both are played the same moves, so any difference comes from the random stream
+        play(&mut [&mut game, &mut resumed], 400);
+        assert_eq!(serde_json::to_value(resumed.state()).unwrap(), serde_json::to_value(game.state()).unwrap());
+    }
+}
@@ -0,0 +1,527 @@
+//! Information-set Monte Carlo search, the Master computer player.
+//! The opponent's fleet, their hand and both decks are hidden, so instead of searching the one real game
+//! it searches many games that could be the real one. Each iteration deals a fresh guess at the hidden
+//! parts (a fleet layout that fits everything seen on the guess board, and cards from the deck), then plays
+//! every candidate action on it and plays the game on for a few turns with quick hunt and target players.
+//! The candidates share each dealt game and the dice rolled after it, so the differences between their
+//! scores come from the actions rather than luck, and the best total is played once the budget is spent.
+//! It never looks at the real opponent board: `Hidden::deal` copies the game and replaces everything the player can't see.
+
+/* ------ Import Used Libraries ------ */
+use crate::action::Action;
+use crate::ai::*;
+use crate::base::*;
+use crate::game::{Game, Phase};
+use crate::rules::Mode;
+use crate::twist::{ActionType, Deck};
+// Random library
+use ::rand::prelude::*;
+use ::rand::rngs::StdRng;
+use std::time::{Duration, Instant};
+
+/*------ Constants ------ */
+pub const DEFAULT_ITERATIONS: usize = 2_000; // Playouts a move gets unless told otherwise, up to 60 ms in a release build and ten times that in a debug one
+pub const DEFAULT_THINK_TIME: Duration = Duration::from_millis(100); // Time a move gets in the window, where keeping up matters more than repeating a game
+const CARD_CANDIDATES: usize = 3;            // Best uses of each card other than the missile that are searched
+const PLAYOUT_TURNS: usize = 6;              // Turns played after the candidate before the position is scored
+const MAX_PLAYOUT_ACTIONS: usize = 200;      // Stops a playout that isn't getting anywhere
+const SAMPLE_ATTEMPTS: usize = 20;           // Tries at dealing a fleet before trusting less of the guess board
+const KNOWN_WEIGHT: f64 = 4.0;               // How much more a placement is worth for each known ship cell it covers
+const REINFORCED_VALUE: f64 = 0.5;           // A reinforced cell is worth this much of a hit saved when scoring
+const FOUND_VALUE: f64 = 0.5;                // A ship cell found by radar is worth this much of a hit when scoring
+
+/*------ Enums and Structs ------ */
+/// How long the search gets to think about each move, as a number of playouts or as time on the clock.
+#[derive(Clone, Copy, PartialEq, Debug)] // Copy - Enables bitwise copying of the type. PartialEq - Allows comparison of this type. Debug - Allows printing with {:?}
+pub enum Budget {
+    Iterations(usize), // A fixed number of playouts, a seeded game always plays out the same way
+    Time(Duration),    // As many playouts as fit in the time, stronger on a faster machine but not repeatable
+}
+
+/// Searches ahead by playing out games that fit what it has seen, see the module notes.
+/// Which cell a missile goes at is left to probability density, the search decides whether a missile or
+/// one of the other cards is the better play, so in Classic it plays like Hard without searching.
+/// When no fleet fits at all it falls back to weighing up its cards like Hard.
+pub struct MonteCarlo {
+    budget: Budget,                  // Thinking time for each move
+    targeting: ProbabilityDensity,   // Picks the missile's cell
+    fallback: CardPlayer,            // Plays when the guess board can't be explained
+    rng: StdRng,                     // Deals the hidden parts and drives the playouts
+}
+
+/// A quick player for playouts: fires at ship cells radar has found, then like `HuntTarget`,
+/// and plays any other card at random.
+struct PlayoutPlayer {
+    hunt: HuntTarget, // Picks the cells to hunt and target
+    rng: StdRng,      // Picks between them
+}
+
+/// Everything `id` can't see in a game, worked out once a move so each iteration only has to deal it.
+struct Hidden {
+    afloat: Vec<(ShipType, String, usize)>, // Type, name and length of each of the opponent's ships that isn't sunk, where they are is hidden
+    tiers: Vec<Tier>,                       // Ways of reading the guess board, most trusting first
+    tier: usize,                            // First tier that hasn't failed yet this move
+}
+
+/// One way of reading the guess board when dealing a fleet.
+struct Tier {
+    blocked: Vec<Vec<bool>>,             // Cells believed to be water or sunk
+    required: Vec<(usize, usize)>,       // Cells believed to be unsunk ships
+    fits: Vec<Vec<Vec<(usize, usize)>>>, // Every placement of each hidden ship that misses `blocked`
+}
+
+/* ------ Struct Implementations ------ */
+impl MonteCarlo {
+    /// Creates the opponent, with an iteration budget the same seed always makes the same choices in the same game.
+    pub fn new(budget: Budget, seed: u64) -> Self {
+        MonteCarlo {
+            budget,
+            // Kept apart from the streams the fallback seeds from `seed` to `seed + 2`
+            targeting: ProbabilityDensity::new(seed.wrapping_add(3)),
+            fallback: CardPlayer::new(Box::new(ProbabilityDensity::new(seed)), seed),
+            rng: StdRng::seed_from_u64(seed.wrapping_add(4)),
+        }
+    }
+
+    /// The actions worth searching: the missile probability density picks and the best few uses of each other
+    /// card going by the scores `CardPlayer` uses, plus passing when that is allowed.
+    fn candidates(&mut self, game: &Game, id: PlayerId, legal: &[Action]) -> Vec<Action> {
+        let mut candidates = Vec::new();
+        if legal.iter().any(|action| matches!(action, Action::Missile { .. })) {
+            candidates.push(self.targeting.choose_action(game, id));
+        }
+        if legal.iter().all(|action| matches!(action, Action::Missile { .. } | Action::EndTurn)) {
+            return candidates;
+        }
+
+        let chances = hit_chances(game, id);
+        let threat = hit_chances(game, id.other());
+        for card in [ActionType::Torpedo, ActionType::RadarScan, ActionType::Reinforce, ActionType::Patrol] {
+            let mut scored: Vec<(f64, Action)> = legal.iter()
+                .filter(|action| action.card() == Some(card))
+                .map(|&action| (CardPlayer::score(game, id, action, &chances, &threat), action))
+                .collect();
+            scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+            candidates.extend(scored.into_iter().take(CARD_CANDIDATES).map(|(_, action)| action));
+        }
+        if legal.contains(&Action::EndTurn) {
+            candidates.push(Action::EndTurn);
+        }
+        candidates
+    }
+
+    /// Plays `action` on a copy of `world` then plays on for a few turns, and scores the result for `id`.
+    /// The same `seed` rolls the same dice, so playouts of different actions from one world can be compared.
+    fn playout(world: &Game, id: PlayerId, action: Action, seed: u64) -> f64 {
+        let mut world = world.sandbox(seed);
+        if world.apply(action).is_err() {
+            return 0.0;
+        }
+
+        let mut players = [PlayoutPlayer::new(seed), PlayoutPlayer::new(seed.wrapping_add(1))];
+        let mut turns = 0;
+        for _ in 0..MAX_PLAYOUT_ACTIONS {
+            match world.phase {
+                Phase::GameOver { winner, .. } => return if winner == id { 1.0 } else { 0.0 },
+                Phase::Handoff => {
+                    turns += 1;
+                    if turns > PLAYOUT_TURNS {
+                        break;
+                    }
+                    world.begin_turn();
+                }
+                Phase::Turn(current) => {
+                    let seat = if current == PlayerId::One { 0 } else { 1 };
+                    let action = players[seat].choose_action(&world, current);
+                    if world.apply(action).is_err() && world.apply(Action::EndTurn).is_err() {
+                        break;
+                    }
+                }
+                Phase::Setup => world.start(),
+            }
+        }
+        evaluate(&world, id)
+    }
+}
+
+impl PlayoutPlayer {
+    /// Creates the player, the same seed always makes the same choices in the same playout.
+    fn new(seed: u64) -> Self {
+        PlayoutPlayer { hunt: HuntTarget::new(seed), rng: StdRng::seed_from_u64(seed) }
+    }
+
+    /// Picks the next action for `id`. Skips building the legal actions while it has a missile to fire,
+    /// so the action can be refused, the caller ends the turn if it is.
+    fn choose_action(&mut self, game: &Game, id: PlayerId) -> Action {
+        if game.acted {
+            return Action::EndTurn;
+        }
+        let player = game.player(id);
+        if game.rules.mode == Mode::Twist && !player.hand.contains(&ActionType::Missile) {
+            return game.legal_actions(id).choose(&mut self.rng).copied().unwrap_or(Action::EndTurn);
+        }
+
+        let board = &player.base.guess_board;
+        let mut cells: Vec<(usize, usize)> = (0..board.height)
+            .flat_map(|x| (0..board.width).map(move |y| (x, y)))
+            .filter(|&(x, y)| matches!(board.cells[x][y], Cells::Occupied | Cells::Reinforced))
+            .collect();
+        if cells.is_empty() {
+            cells = self.hunt.target_cells(&player.base);
+        }
+        if cells.is_empty() {
+            cells = self.hunt.hunt_cells(game, &player.base);
+        }
+        cells.choose(&mut self.rng).map_or(Action::EndTurn, |&(x, y)| Action::Missile { x, y })
+    }
+}
+
+impl Hidden {
+    /// Works out what `id` knows about the opponent's fleet.
+    /// Patrols can move ships under old misses and scans, so as well as trusting everything on the guess board
+    /// there are tiers that stop trusting misses and then scans, like `probability_density`, in the last
+    /// only the hits have to be covered.
+    fn new(game: &Game, id: PlayerId) -> Self {
+        let me = &game.player(id).base;
+        let board = &me.guess_board;
+        let scanned = &game.player(id).scanned_cells;
+
+        // Sunk ships are out in the open, the rest of the fleet could be anywhere that fits
+        let mut afloat: Vec<(ShipType, String, usize)> = game.rules.fleet.entries.iter()
+            .flat_map(|entry| std::iter::repeat_n((entry.ship_type, entry.display_name(), entry.length), entry.count))
+            .collect();
+        for ship in &me.sunk_ships {
+            if let Some(i) = afloat.iter().position(|&(ship_type, _, length)| ship_type == ship.ship_type && length == ship.positions.len()) {
+                afloat.remove(i);
+            }
+        }
+
+        let hits = open_hits(me);
+        let mut known = hits.clone();
+        for x in 0..board.height {
+            for y in 0..board.width {
+                if matches!(board.cells[x][y], Cells::Occupied | Cells::Reinforced) {
+                    known.push((x, y));
+                }
+            }
+        }
+
+        let readings = [(true, &scanned[..], known.clone()), (false, &scanned[..], known), (false, &[][..], hits)];
+        let tiers = readings.into_iter()
+            .map(|(trust_misses, scans, required)| {
+                let blocked = blocked_cells(game, id, trust_misses, scans);
+                let fits = afloat.iter().map(|&(_, _, length)| placements(&blocked, length)).collect();
+                Tier { blocked, required, fits }
+            })
+            .collect();
+        Hidden { afloat, tiers, tier: 0 }
+    }
+
+    /// A copy of `game` with everything `id` can't see dealt afresh: the opponent's unsunk ships are laid out
+    /// again to fit `id`'s guess board, the opponent's hand and deck are new cards, and `id`'s own deck is shuffled.
+    /// None if no layout fits at all.
+    fn deal(&mut self, game: &Game, id: PlayerId, rng: &mut StdRng) -> Option<Game> {
+        let layout = loop {
+            let tier = self.tiers.get(self.tier)?;
+            if let Some(layout) = (0..SAMPLE_ATTEMPTS).find_map(|_| try_layout(game, tier, rng)) {
+                break layout;
+            }
+            // Tiers that failed once are skipped for the rest of the move
+            self.tier += 1;
+        };
+
+        let mut world = game.sandbox(rng.random());
+        let seat = if id == PlayerId::One { 0 } else { 1 };
+        let guess = &game.player(id).base.guess_board;
+
+        // The sunk ships are kept as they were seen, the rest are built afresh wherever they were dealt
+        let opponent = &mut world.players[1 - seat];
+        opponent.base.ships = game.player(id).base.sunk_ships.clone();
+        for ((ship_type, name, _), positions) in self.afloat.iter().zip(layout) {
+            let vertical = positions.len() > 1 && positions[0].1 == positions[1].1;
+            opponent.base.ships.push(Ship {
+                ship_type: *ship_type,
+                name: name.clone(),
+                positions,
+                orientation: if vertical { Orientation::Verticle } else { Orientation::Horizontal },
+            });
+        }
+        let board = &mut opponent.base.board;
+        for x in 0..board.height {
+            for y in 0..board.width {
+                board.cells[x][y] = match guess.cells[x][y] {
+                    Cells::Miss => Cells::Miss,
+                    _ => Cells::Empty,
+                };
+            }
+        }
+        for ship in &opponent.base.ships {
+            for &(x, y) in &ship.positions {
+                opponent.base.board.cells[x][y] = match guess.cells[x][y] {
+                    Cells::Hit => Cells::Hit,
+                    Cells::Reinforced => Cells::Reinforced,
+                    _ => Cells::Occupied,
+                };
+            }
+        }
+        opponent.base.update_ship_count();
+
+        if game.rules.mode == Mode::Twist {
+            // The opponent's cards could be any of a fresh deck, only how many they hold is known
+            let mut cards = Deck::new();
+            cards.build();
+            cards.shuffle(rng);
+            let hand_size = opponent.hand.len().min(cards.deck_list.len());
+            let rest = cards.deck_list.split_off(hand_size);
+            opponent.hand = cards.deck_list;
+            opponent.deck.deck_list = rest.into_iter().take(opponent.deck.deck_list.len()).collect();
+
+            world.players[seat].deck.shuffle(rng);
+        }
+        Some(world)
+    }
+}
+
+/* ------ Trait Implementations ------ */
+/// The budget a Master player gets unless told otherwise
+impl Default for Budget {
+    fn default() -> Self {
+        Budget::Iterations(DEFAULT_ITERATIONS)
+    }
+}
+
+impl Opponent for MonteCarlo {
+    fn choose_action(&mut self, game: &Game, id: PlayerId) -> Action {
+        let legal = game.legal_actions(id);
+        if legal.len() <= 1 {
+            return legal.first().copied().unwrap_or(Action::EndTurn);
+        }
+        let mut candidates: Vec<(Action, f64)> = self.candidates(game, id, &legal).into_iter()
+            .map(|action| (action, 0.0))
+            .collect();
+        if candidates.len() == 1 {
+            return candidates[0].0;
+        }
+
+        let mut hidden = Hidden::new(game, id);
+        let started = Instant::now();
+        let mut playouts = 0;
+        loop {
+            let done = match self.budget {
+                Budget::Iterations(limit) => playouts >= limit,
+                Budget::Time(limit) => started.elapsed() >= limit,
+            };
+            // Every candidate gets at least one playout whatever the budget
+            if done && playouts > 0 {
+                break;
+            }
+
+            let Some(world) = hidden.deal(game, id, &mut self.rng) else {
+                return self.fallback.choose_action(game, id);
+            };
+            let seed = self.rng.random();
+            for (action, total) in &mut candidates {
+                *total += MonteCarlo::playout(&world, id, *action, seed);
+            }
+            playouts += candidates.len();
+        }
+
+        // Every candidate had the same worlds, so the best total is the best average
+        candidates.iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(Action::EndTurn, |&(action, _)| action)
+    }
+
+    fn study(&mut self, ship_heat: &[Vec<f64>]) {
+        self.targeting.study(ship_heat);
+        self.fallback.study(ship_heat);
+    }
+}
+
+/* ------ Functions ------ */
+/// One attempt at laying out the hidden ships from a tier: ships are first put through its required cells
+/// that are still uncovered, then the rest go anywhere left. None if it paints itself into a corner.
+fn try_layout(game: &Game, tier: &Tier, rng: &mut StdRng) -> Option<Vec<Vec<(usize, usize)>>> {
+    let required = &tier.required;
+    let mut taken: Vec<Vec<bool>> = tier.blocked.clone();
+    let mut layout: Vec<Option<&Vec<(usize, usize)>>> = vec![None; tier.fits.len()];
+    let mut covered = vec![false; required.len()];
+    let free = |taken: &[Vec<bool>], cells: &[(usize, usize)]| cells.iter().all(|&(x, y)| !taken[x][y]);
+
+    while let Some(need) = covered.iter().position(|&done| !done) {
+        // Any unplaced ship through the first uncovered cell, placements through more of them count for more
+        let mut options = Vec::new();
+        for (ship, placements) in tier.fits.iter().enumerate() {
+            if layout[ship].is_some() {
+                continue;
+            }
+            for cells in placements {
+                if cells.contains(&required[need]) && free(&taken, cells) {
+                    let through = required.iter().filter(|cell| cells.contains(cell)).count();
+                    options.push((KNOWN_WEIGHT.powi(through as i32), ship, cells));
+                }
+            }
+        }
+        let &(_, ship, cells) = options.choose_weighted(rng, |option| option.0).ok()?;
+        place(game, &mut taken, cells);
+        for (i, cell) in required.iter().enumerate() {
+            covered[i] |= cells.contains(cell);
+        }
+        layout[ship] = Some(cells);
+    }
+
+    for (ship, placements) in tier.fits.iter().enumerate() {
+        if layout[ship].is_none() {
+            let open: Vec<&Vec<(usize, usize)>> = placements.iter().filter(|cells| free(&taken, cells)).collect();
+            let cells = *open.choose(rng)?;
+            place(game, &mut taken, cells);
+            layout[ship] = Some(cells);
+        }
+    }
+    layout.into_iter().map(|cells| cells.cloned()).collect()
+}
+
+/// Every way a ship of `length` fits on the board without covering a `blocked` cell.
+fn placements(blocked: &[Vec<bool>], length: usize) -> Vec<Vec<(usize, usize)>> {
+    let (height, width) = (blocked.len(), blocked[0].len());
+    let mut found = Vec::new();
+    for horizontal in [true, false] {
+        if length == 1 && !horizontal {
+            continue; // A single cell ship is the same both ways round
+        }
+        for x in 0..height {
+            for y in 0..width {
+                let cells: Vec<(usize, usize)> = (0..length)
+                    .map(|i| if horizontal { (x, y + i) } else { (x + i, y) })
+                    .collect();
+                if cells.iter().all(|&(cx, cy)| cx < height && cy < width && !blocked[cx][cy]) {
+                    found.push(cells);
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Marks a placed ship's cells as taken, and the cells around it when ships can't touch.
+fn place(game: &Game, taken: &mut [Vec<bool>], cells: &[(usize, usize)]) {
+    let (height, width) = (taken.len(), taken[0].len());
+    for &(x, y) in cells {
+        taken[x][y] = true;
+        if game.rules.no_touching {
+            for row in &mut taken[x.saturating_sub(1)..=(x + 1).min(height - 1)] {
+                row[y.saturating_sub(1)..=(y + 1).min(width - 1)].fill(true);
+            }
+        }
+    }
+}
+
+/// How good `world` looks for `id`, from 0 to 1: half way plus half the difference between the share of
+/// the opponent's ship cells `id` has hit and the share of its own the opponent has, counting reinforced cells
+/// as partly saved and ship cells found by radar as partly hit.
+fn evaluate(world: &Game, id: PlayerId) -> f64 {
+    // How far `attacker` is towards sinking `target`'s fleet
+    let damage = |attacker: &BasePlayer, target: &BasePlayer| -> f64 {
+        let mut hits = 0.0;
+        let mut cells = 0.0;
+        for ship in &target.ships {
+            for &(x, y) in &ship.positions {
+                cells += 1.0;
+                match target.board.cells[x][y] {
+                    Cells::Hit => hits += 1.0,
+                    Cells::Reinforced => hits -= REINFORCED_VALUE,
+                    _ => {}
+                }
+                if matches!(attacker.guess_board.cells[x][y], Cells::Occupied | Cells::Reinforced) {
+                    hits += FOUND_VALUE;
+                }
+            }
+        }
+        if cells == 0.0 { 0.0 } else { hits / cells }
+    };
+    let (me, them) = (&world.player(id).base, &world.player(id.other()).base);
+    let dealt = damage(me, them);
+    let taken = damage(them, me);
+    (0.5 + 0.5 * (dealt - taken)).clamp(0.0, 1.0)
+}
+
+/* ------ Tests ------ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::rules::Rules;
+
+    /// A Twist game between two Hard players, stopped at the start of a turn of Player 1's once they have sunk a ship
+    fn game_under_way(seed: u64) -> Game {
+        let mut game = Game::with_seed(Rules::new(Mode::Twist), seed).unwrap();
+        let mut players = [Difficulty::Hard.opponent(seed), Difficulty::Hard.opponent(seed + 1)];
+        game.start();
+        loop {
+            match game.phase {
+                Phase::Turn(PlayerId::One) if !game.acted && !game.player(PlayerId::One).base.sunk_ships.is_empty() => return game,
+                Phase::Turn(id) => {
+                    let action = players[if id == PlayerId::One { 0 } else { 1 }].choose_action(&game, id);
+                    game.apply(action).unwrap();
+                }
+                Phase::Handoff => game.begin_turn(),
+                _ => panic!("the game ended before Player 1 sank anything"),
+            }
+        }
+    }
+
+    #[test]
+    fn search_picks_a_legal_action() {
+        for seed in 0..3 {
+            let game = game_under_way(seed);
+            let action = MonteCarlo::new(Budget::Iterations(50), seed).choose_action(&game, PlayerId::One);
+            assert!(game.legal_actions(PlayerId::One).contains(&action), "{:?} isn't allowed", action);
+        }
+    }
+
+    #[test]
+    fn same_seed_searches_the_same_way() {
+        for seed in 0..3 {
+            let game = game_under_way(seed);
+            let picks: Vec<Action> = (0..2)
+                .map(|_| MonteCarlo::new(Budget::Iterations(100), 7).choose_action(&game, PlayerId::One))
+                .collect();
+            assert_eq!(picks[0], picks[1]);
+        }
+    }
+
+    #[test]
+    fn dealt_fleets_fit_the_guess_board() {
+        let game = game_under_way(4);
+        let guess = &game.player(PlayerId::One).base.guess_board;
+        let mut hidden = Hidden::new(&game, PlayerId::One);
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let world = hidden.deal(&game, PlayerId::One, &mut rng).unwrap();
+            let fleet = &world.player(PlayerId::Two).base;
+            assert_eq!(fleet.ships.len(), game.rules.fleet.ship_count());
+            for x in 0..guess.height {
+                for y in 0..guess.width {
+                    let ship = fleet.ships.iter().any(|ship| ship.positions.contains(&(x, y)));
+                    if matches!(guess.cells[x][y], Cells::Hit | Cells::Occupied | Cells::Reinforced) {
+                        assert!(ship, "no ship dealt under ({}, {})", x, y);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dealing_never_looks_at_the_hidden_fleet() {
+        let game = game_under_way(5);
+        // Same game as far as Player 1 can tell, with Player 2's ships listed in another order
+        let mut shuffled = game.sandbox(0);
+        shuffled.players[1].base.ships.reverse();
+
+        let dealt = |game: &Game| {
+            let world = Hidden::new(game, PlayerId::One).deal(game, PlayerId::One, &mut StdRng::seed_from_u64(2)).unwrap();
+            world.player(PlayerId::Two).base.ships.iter().map(|ship| ship.positions.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(dealt(&game), dealt(&shuffled));
+    }
+}
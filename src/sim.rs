@@ -0,0 +1,241 @@
+//! Headless matches between computer players.
+//! Plays many games back to back with no window and adds up how each side did, so a change to an AI
+//! or to the deck can be judged on numbers instead of by playtesting. Seats are swapped every game so
+//! neither side always gets the first shot. `battleships-sim` is the command line front end.
+
+/* ------ Import Used Libraries ------ */
+use crate::action::Action;
+use crate::ai::Opponent;
+use crate::base::PlayerId;
+use crate::error::RulesError;
+use crate::game::{Game, Phase};
+use crate::placement::Strategy;
+use crate::rules::{Mode, Rules};
+use crate::twist::ActionType;
+
+/*------ Constants ------ */
+const MAX_ACTIONS: usize = 20_000; // Actions before a game is given up on as stuck
+const SECOND_SEAT_SALT: u64 = 0x9E37_79B9_7F4A_7C15; // Mixed into the seed for the second seat so the two sides don't share random streams
+
+/*------ Enums and Structs ------ */
+/// One side of a match.
+pub struct Entrant {
+    pub name: String,                                 // Shown in the results
+    pub player: Box<dyn Fn(u64) -> Box<dyn Opponent>>, // Creates the player for one game from a seed
+    pub layout: Box<dyn Fn(u64) -> Strategy>,         // Picks its fleet layout for one game from the game's seed
+}
+
+/// How one side did over a whole match.
+#[derive(Clone, Default, Debug)] // Clone - Creates a deep copy of the value. Default - Starts every count at zero. Debug - Allows printing with {:?}
+pub struct SideStats {
+    pub wins: usize,                     // Games won
+    pub win_turns: Vec<u32>,             // Turns each win took, sorted once the match is over (wins by forfeit aren't in it)
+    pub forfeits: usize,                 // Games lost by resigning or picking an action that isn't allowed
+    pub shots: usize,                    // Missiles and torpedoes fired
+    pub sinks: usize,                    // Ships sunk
+    pub cards: Vec<(ActionType, usize)>, // How often each card was played, Twist only
+}
+
+/// Results of a whole match.
+#[derive(Clone, Debug)] // Clone - Creates a deep copy of the value. Debug - Allows printing with {:?}
+pub struct MatchStats {
+    pub games: usize,          // Games played
+    pub unfinished: usize,     // Games given up on after `MAX_ACTIONS` actions
+    pub sides: [SideStats; 2], // The first entrant then the second
+}
+
+/* ------ Struct Implementations ------ */
+impl SideStats {
+    /// Share of `games` this side won, from 0 to 1.
+    pub fn win_rate(&self, games: usize) -> f64 {
+        if games == 0 { 0.0 } else { self.wins as f64 / games as f64 }
+    }
+
+    /// Average turns a win took, None without any wins.
+    pub fn mean_turns(&self) -> Option<f64> {
+        if self.win_turns.is_empty() {
+            return None;
+        }
+        Some(self.win_turns.iter().map(|&turns| turns as f64).sum::<f64>() / self.win_turns.len() as f64)
+    }
+
+    /// Turns within which `percent` of the wins were over (nearest rank), None without any wins.
+    pub fn percentile_turns(&self, percent: f64) -> Option<u32> {
+        if self.win_turns.is_empty() {
+            return None;
+        }
+        let rank = (percent / 100.0 * self.win_turns.len() as f64).ceil() as usize;
+        Some(self.win_turns[rank.clamp(1, self.win_turns.len()) - 1])
+    }
+
+    /// Missiles and torpedoes fired for every ship sunk, None if nothing was sunk.
+    pub fn shots_per_sink(&self) -> Option<f64> {
+        if self.sinks == 0 { None } else { Some(self.shots as f64 / self.sinks as f64) }
+    }
+
+    /// Times `card` was played
+    pub fn card_count(&self, card: ActionType) -> usize {
+        self.cards.iter().find(|(played, _)| *played == card).map_or(0, |&(_, count)| count)
+    }
+
+    /// Counts one play of `card`.
+    fn count_card(&mut self, card: ActionType) {
+        match self.cards.iter_mut().find(|(played, _)| *played == card) {
+            Some((_, count)) => *count += 1,
+            None => self.cards.push((card, 1)),
+        }
+    }
+}
+
+/* ------ Functions ------ */
+/// Plays `games` games of `rules` between two entrants, starting from `first_seed` and counting up one seed
+/// per game, so the same arguments always give the same results. The first entrant sits in Player 1's seat in
+/// even games and Player 2's in odd ones. Fails if the rules don't describe a playable game.
+pub fn run_match(rules: &Rules, entrants: [&Entrant; 2], games: usize, first_seed: u64) -> Result<MatchStats, RulesError> {
+    let mut stats = MatchStats { games, unfinished: 0, sides: [SideStats::default(), SideStats::default()] };
+
+    for game_index in 0..games {
+        let seed = first_seed.wrapping_add(game_index as u64);
+        // seats[i] is the entrant playing Player i + 1
+        let seats = if game_index % 2 == 0 { [0, 1] } else { [1, 0] };
+        let layouts = [(entrants[seats[0]].layout)(seed), (entrants[seats[1]].layout)(seed)];
+        let mut players = [(entrants[seats[0]].player)(seed), (entrants[seats[1]].player)(seed ^ SECOND_SEAT_SALT)];
+
+        let mut game = Game::with_layouts(rules.clone(), seed, layouts)?;
+        for id in [PlayerId::One, PlayerId::Two] {
+            players[seat(id)].join(&mut game, id);
+        }
+        game.start();
+
+        let mut actions = 0;
+        loop {
+            match game.phase {
+                Phase::GameOver { winner, turns } => {
+                    let side = &mut stats.sides[seats[seat(winner)]];
+                    side.wins += 1;
+                    side.win_turns.push(turns);
+                    break;
+                }
+                Phase::Handoff => game.begin_turn(),
+                Phase::Turn(id) => {
+                    actions += 1;
+                    if actions > MAX_ACTIONS {
+                        stats.unfinished += 1;
+                        break;
+                    }
+
+                    let action = players[seat(id)].choose_action(&game, id);
+                    if action == Action::Resign || game.apply(action).is_err() {
+                        // Resigning or picking something that isn't allowed forfeits the game
+                        let _ = game.resign();
+                        stats.sides[seats[seat(id)]].forfeits += 1;
+                        stats.sides[seats[seat(id.other())]].wins += 1;
+                        break;
+                    }
+
+                    let side = &mut stats.sides[seats[seat(id)]];
+                    if matches!(action, Action::Missile { .. } | Action::Torpedo { .. }) {
+                        side.shots += 1;
+                    }
+                    if let (Mode::Twist, Some(card)) = (game.rules.mode, action.card()) {
+                        side.count_card(card);
+                    }
+                }
+                Phase::Setup => game.start(),
+            }
+        }
+
+        for id in [PlayerId::One, PlayerId::Two] {
+            stats.sides[seats[seat(id)]].sinks += game.player(id).base.sunk_ships.len();
+        }
+    }
+
+    for side in &mut stats.sides {
+        side.win_turns.sort_unstable();
+    }
+    Ok(stats)
+}
+
+/// Index of a player's seat, 0 for Player 1
+fn seat(id: PlayerId) -> usize {
+    match id {
+        PlayerId::One => 0,
+        PlayerId::Two => 1,
+    }
+}
+
+/* ------ Tests ------ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+
+    /// Gives up on its first move
+    struct Resigner;
+
+    impl Opponent for Resigner {
+        fn choose_action(&mut self, _game: &Game, _id: PlayerId) -> Action {
+            Action::Resign
+        }
+    }
+
+    fn entrant(level: Difficulty) -> Entrant {
+        Entrant {
+            name: level.name().to_string(),
+            player: Box::new(move |seed| level.opponent(seed)),
+            layout: Box::new(|_| Strategy::Random),
+        }
+    }
+
+    #[test]
+    fn every_game_is_counted() {
+        let (hard, easy) = (entrant(Difficulty::Hard), entrant(Difficulty::Easy));
+        let stats = run_match(&Rules::new(Mode::Classic), [&hard, &easy], 6, 1).unwrap();
+        assert_eq!(stats.unfinished, 0);
+        assert_eq!(stats.sides[0].wins + stats.sides[1].wins, 6);
+        assert!(stats.sides[0].wins > stats.sides[1].wins);
+        for side in &stats.sides {
+            assert_eq!(side.win_turns.len(), side.wins);
+            assert!(side.win_turns.is_sorted());
+            assert!(side.shots >= side.sinks);
+            assert!(side.cards.is_empty());
+        }
+        // Every win sank the whole fleet
+        assert!(stats.sides[0].sinks >= stats.sides[0].wins * 5);
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_results() {
+        let (normal, easy) = (entrant(Difficulty::Normal), entrant(Difficulty::Easy));
+        let rules = Rules::new(Mode::Twist);
+        let first = run_match(&rules, [&normal, &easy], 2, 9).unwrap();
+        let second = run_match(&rules, [&normal, &easy], 2, 9).unwrap();
+        assert_eq!(format!("{:?}", first), format!("{:?}", second));
+        assert!(first.sides[0].card_count(ActionType::Missile) > 0);
+    }
+
+    #[test]
+    fn resigning_forfeits_the_game() {
+        let quitter = Entrant {
+            name: "Quitter".to_string(),
+            player: Box::new(|_| Box::new(Resigner)),
+            layout: Box::new(|_| Strategy::Random),
+        };
+        let easy = entrant(Difficulty::Easy);
+        let stats = run_match(&Rules::new(Mode::Classic), [&quitter, &easy], 4, 0).unwrap();
+        assert_eq!(stats.sides[0].forfeits, 4);
+        assert_eq!(stats.sides[1].wins, 4);
+        // Forfeits aren't wins that took a number of turns
+        assert_eq!(stats.sides[1].mean_turns(), None);
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let side = SideStats { wins: 4, win_turns: vec![10, 20, 30, 40], ..SideStats::default() };
+        assert_eq!(side.mean_turns(), Some(25.0));
+        assert_eq!(side.percentile_turns(10.0), Some(10));
+        assert_eq!(side.percentile_turns(50.0), Some(20));
+        assert_eq!(side.percentile_turns(90.0), Some(40));
+        assert_eq!(side.win_rate(8), 0.5);
+    }
+}
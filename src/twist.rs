@@ -1,16 +1,18 @@
 //! implements the Twisted game mode with action cards and enhanced gameplay mechanics.
 //! Extends the base Battleship logic with strategic abilities like ship movement and special attacks.
+//! Like `base.rs` this is pure game logic, the cards and patrol highlight are drawn by `ui.rs`.
 
  /* ------ Import Used Libraries ------ */
  // Import the base game
 use super::base::*;
+// Errors returned by actions
+use crate::error::ActionError;
+// Feedback sent to the ui and anything else listening
+use crate::events::{EventBus, GameEvent};
 // Random library
-use ::rand::prelude::SliceRandom;
-// Graphics library
-use macroquad:: prelude::*;
-// A module I recompiled and made small fixes to, but did not write. Used for grid graphics and logic.
-// Origional code avaliable here: https://github.com/TheDinner22/macroquad_grid
-extern crate macroquad_grid_dex;
+use ::rand::prelude::{Rng, SliceRandom};
+// Saving and loading
+use serde::{Deserialize, Serialize};
 
 /*------ Constants ------ */
 /// The number of action cards a player holds at any time.
@@ -20,7 +22,7 @@ pub const DECK_SIZE: usize = 48;
 
 /*------ Enums and Structs ------ */
 /// Types of action cards available in the Twisted mode.
-#[derive(Clone, Copy,PartialEq)] // Copy - Enables bitwise copying of the type, doesn't move ownership. Clone - Creates a deep copy of the value, can proform complex copying. PartialEq - Allows comparison of this type.
+#[derive(Clone, Copy,PartialEq, Debug, Serialize, Deserialize)] // Copy - Enables bitwise copying of the type, doesn't move ownership. Clone - Creates a deep copy of the value, can proform complex copying. PartialEq - Allows comparison of this type. Serialize/Deserialize - Allows saving and loading.
 pub enum ActionType {
     Missile,     // Missle is the base battle ships fire ability
     Torpedo,    // Torpedo fires from a point on the x axis then shots upwards along the y axis
@@ -30,17 +32,21 @@ pub enum ActionType {
 }
 
 /// Manages the deck of action cards.
+#[derive(Clone, Serialize, Deserialize)] // Clone - Creates a deep copy of the value, so a search can play on a copy of the game. Serialize/Deserialize - Allows saving and loading
 pub struct Deck { 
     pub deck_list: Vec<ActionType>, // All cards (shuffled during gameplay)
 }
 
 /// Extends BasePlayer with Twisted mode features.
+#[derive(Clone, Serialize, Deserialize)] // Clone - Creates a deep copy of the value, so a search can play on a copy of the game. Serialize/Deserialize - Allows saving and loading
 pub struct TwistPlayer {
     pub base: BasePlayer, // Inherits core Battleship logic
 
     pub deck: Deck, // Drawable action cards
     pub hand: Vec<ActionType>, // Current available actions
 
+    pub scanned_cells: Vec<(usize, usize)>, // Guess board cells revealed by radar scans, most recent last
+
     // Patrol system state
     pub patrol_mode: bool, // True when moving a ship
     pub patrol_ship: Option<usize>, // Index of moving ship
@@ -94,10 +100,9 @@ impl Deck {
         }
     }
 
-    /// Shuffles the deck using Rust’s built-in RNG for randomizing the order.
-    pub fn shuffle(&mut self) {
-        let mut rng = ::rand::rng();
-        self.deck_list.shuffle(&mut rng);
+    /// Shuffles the deck with the game's RNG, so a seeded game always deals the same cards.
+    pub fn shuffle(&mut self, rng: &mut (impl Rng + ?Sized)) {
+        self.deck_list.shuffle(rng);
     }
 }
 
@@ -108,207 +113,188 @@ impl TwistPlayer {
     /// - Randomized ship placement (from BasePlayer)
     /// - Shuffled deck of action cards
     /// - Empty starting hand (filled via draw_hand())
-    pub fn new(base_player: BasePlayer) -> Self {
-        let mut p = TwistPlayer {
+    pub fn new(base_player: BasePlayer, rng: &mut (impl Rng + ?Sized)) -> Self {
+        let mut p = TwistPlayer::classic(base_player);
+
+        p.deck.build();
+        p.deck.shuffle(rng);
+        p.draw_hand(&mut EventBus::new(), rng); // Nobody is listening before the game starts
+
+        p
+    }
+
+    /// Wraps a player for Classic mode, with an empty deck and hand since every turn is a missile.
+    pub fn classic(base_player: BasePlayer) -> Self {
+        TwistPlayer {
             base: base_player,
 
             deck: Deck::new(),
             hand: Vec::new(),
 
+            scanned_cells: Vec::new(),
+
             // Whether the player is currently selecting a patrol move
             patrol_mode: false,
             // Index of the currently selected ship for movement, if any
             patrol_ship: None,
             // Countdown timer (in frames) before patrol mode times out (30 frames ≈ 0.5s)
             patrol_frames: 0,
-        };
-
-        p.deck.build();
-        p.deck.shuffle();
-        p.draw_hand();
-
-        return p;
+        }
     }
 
     /// Fires a torpedo attack along a vertical column:
-    /// 1. Starts at bottom of grid (x = GRID_SIZE - 1)
+    /// 1. Starts at bottom of grid (the last row)
     /// 2. Moves upward until hitting a ship or leaving grid
-    /// 3. Updates both players' boards visually
-    /// Returns true if any target was hit.
-    pub fn fire_torpedo(&mut self, opponent: &mut TwistPlayer, target_y: usize) -> bool {
-        let mut x = crate::base::GRID_SIZE - 1;
-        let mut hit_something = false;
-    
-        while x < GRID_SIZE {
-            match opponent.base.board.cells[x][target_y] {
-                Cells::Reinforced => {
-                    self.base.guess_board.change_cell(x, target_y, Cells::Occupied, &mut self.base.guessgrid);
-                    opponent.base.board.change_cell(x, target_y, Cells::Occupied, &mut opponent.base.boardgrid);
-                    println!("Torpedo hit a reinforced cell! Protection removed.");
-                    hit_something = true;
-                    break;
-                }
-                Cells::Occupied => {
-                    self.base.guess_board.change_cell(x, target_y, Cells::Hit, &mut self.base.guessgrid);
-                    opponent.base.board.change_cell(x, target_y, Cells::Hit, &mut opponent.base.boardgrid);
-                    println!("Torpedo hit!");
-                    hit_something = true;
-                    
-                    // Check if this hit destroyed a ship
-                    if let Some(ship_idx) = opponent.base.find_ship_at(x, target_y) {
-                        if opponent.base.is_ship_destroyed(ship_idx) {
-                            println!("Ship completely destroyed by torpedo!");
-                            opponent.base.update_ship_count();
-                        }
-                    }
-                    break;
-                }
-                Cells::Hit => {
-                    println!("Torpedo stopped! Already hit here.");
-                    break;
-                }
-                _ => {
-                    self.base.guess_board.change_cell(x, target_y, Cells::Miss, &mut self.base.guessgrid);
-                    opponent.base.board.change_cell(x, target_y, Cells::Miss, &mut opponent.base.boardgrid);
-                    println!("Torpedo missed!");
-                }
-            }
+    /// 3. Updates both players' boards
+    ///
+    /// Returns every cell the torpedo passed and what it stopped on, fails if the column is off the board.
+    pub fn fire_torpedo(&mut self, opponent: &mut TwistPlayer, target_y: usize, events: &mut EventBus) -> Result<Shot, ActionError> {
+        let mut x = opponent.base.board.height - 1;
+        opponent.base.board.check_bounds(x, target_y)?;
+        let mut cells = Vec::new();
+        let mut outcome = ShotOutcome::Miss;
     
+        while x < opponent.base.board.height {
+            cells.push((x, target_y));
+            outcome = self.base.strike(&mut opponent.base, x, target_y);
+
+            // Only keeps travelling through water
+            if outcome != ShotOutcome::Miss { break; }
             if x == 0 { break; }
             x -= 1;
         }
-    
-        hit_something
-    }
 
-    /// Gets vertical column for torpedo attacks:
-    /// - Maps mouse X-position to guess grid columns
-    /// - Returns `Some(usize)` (0-9) if click within right-side grid
-    /// - Used exclusively for torpedo targeting
-    pub fn get_torpedo_target_column(&self) -> Option<usize> {
-        let (mouse_x, mouse_y) = mouse_position();
-        let grid_x_offset = 700.0;
-        let grid_y_offset = 50.0;
-        let cell_size = 40.0;
-        let grid_size_px = cell_size * GRID_SIZE as f32;
-
-        if mouse_x >= grid_x_offset && mouse_x < grid_x_offset + grid_size_px &&
-           mouse_y >= grid_y_offset && mouse_y < grid_y_offset + grid_size_px {
-            let y = ((mouse_x - grid_x_offset) / cell_size) as usize;
-            return Some(y);
-        }
-
-        None
+        let shot = Shot { outcome, cells };
+        self.base.report_shot(ActionType::Torpedo, &shot, events);
+    
+        Ok(shot)
     }
 
-    /// Scans a 3x3 area centered on (target_x, target_y):
+    /// Scans a cross of 5 cells: (target_x, target_y) and the cells directly above, below, left and right of it.
     /// - Reveals cell states on guess board
+    /// - Remembers the scanned cells so empty water can be shown as revealed
     /// - Works even at grid edges (ignores out-of-bounds cells)
-    pub fn radar_scan(&mut self, opponent: &mut TwistPlayer, target_x: usize, target_y: usize) {
+    ///
+    /// Fails if the centre itself is off the board.
+    pub fn radar_scan(&mut self, opponent: &mut TwistPlayer, target_x: usize, target_y: usize, events: &mut EventBus) -> Result<(), ActionError> {
+        opponent.base.board.check_bounds(target_x, target_y)?;
         let offsets = [(0, 0), (0, 1), (0, -1), (1, 0), (-1, 0)];
     
         for &(dx, dy) in &offsets {
             let nx = target_x as isize + dx;
             let ny = target_y as isize + dy;
     
-            if nx >= 0 && nx < GRID_SIZE as isize && ny >= 0 && ny < GRID_SIZE as isize {
+            if opponent.base.board.in_bounds_signed(nx, ny) {
                 let ux = nx as usize;
                 let uy = ny as usize;
                 let cell = opponent.base.board.cells[ux][uy];
     
-                self.base.guess_board.change_cell(ux, uy, cell, &mut self.base.guessgrid);
+                self.base.guess_board.change_cell(ux, uy, cell);
+                // Kept in the order they were last scanned
+                self.scanned_cells.retain(|&cell| cell != (ux, uy));
+                self.scanned_cells.push((ux, uy));
             }
         }
-        println!("Radar scan complete!");
+        events.emit(GameEvent::RadarScanned { player: self.base.id, x: target_x, y: target_y });
+        Ok(())
     }
 
     /// Attempts to reinforce a ship cell:
     /// - Only works on Occupied cells
-    /// - Fails if cell is already Reinforced
-    /// Returns success status.
-    pub fn reinforce(&mut self, target_x: usize, target_y: usize) -> bool {
+    /// - Fails if cell is already Reinforced or has been hit
+    pub fn reinforce(&mut self, target_x: usize, target_y: usize, events: &mut EventBus) -> Result<(), ActionError> {
+        self.base.board.check_bounds(target_x, target_y)?;
         let current_state = self.base.board.cells[target_x][target_y];
         
         match current_state {
             Cells::Occupied => {
-                self.base.board.change_cell(target_x, target_y, Cells::Reinforced, &mut self.base.boardgrid);
-                println!("Cell at ({}, {}) reinforced!", target_x, target_y);
-                true
-            }
-            Cells::Reinforced => {
-                println!("Cell at ({}, {}) is already reinforced.", target_x, target_y);
-                false
-            }
-            _ => {
-                println!("Cannot reinforce cell at ({}, {}). It must be occupied.", target_x, target_y);
-                false
+                self.base.board.change_cell(target_x, target_y, Cells::Reinforced);
+                events.emit(GameEvent::CellReinforced { player: self.base.id, x: target_x, y: target_y });
+                Ok(())
             }
+            Cells::Reinforced => Err(ActionError::AlreadyReinforced { x: target_x, y: target_y }),
+            Cells::Hit => Err(ActionError::ShipDamaged),
+            _ => Err(ActionError::NotOwnShip { x: target_x, y: target_y }),
         }
     }
 
-    /// Converts mouse position to grid coordinates on the player's OWN board:
-    /// - Uses different grid offset than guess board
-    /// - Returns `Some((x, y))` if within placement grid bounds
-    /// - Used for ship reinforcement and patrol selection
-    pub fn get_clicked_cell_on_own_board(&self) -> Option<(usize, usize)> {
-        let (mouse_x, mouse_y) = mouse_position();
-        let grid_x_offset = 150.0;
-        let grid_y_offset = 50.0;
-        let cell_size = 40.0;
-        let grid_size_px = cell_size * GRID_SIZE as f32;
-
-        if mouse_x >= grid_x_offset && mouse_x < grid_x_offset + grid_size_px &&
-           mouse_y >= grid_y_offset && mouse_y < grid_y_offset + grid_size_px {
-            let x = ((mouse_y - grid_y_offset) / cell_size) as usize;
-            let y = ((mouse_x - grid_x_offset) / cell_size) as usize;
-            return Some((x, y));
-        }
-        None
-    }
-
     /// Enters patrol mode for ship at (x,y):
     /// 1. Validates ship exists and isn't damaged
-    /// 2. Highlights ship with yellow cells
+    /// 2. Marks the ship as the patrol ship (the ui highlights it)
     /// 3. Starts 0.5-second timer (30 frames)
-    /// Returns false if invalid selection.
-    pub fn start_patrol(&mut self, x: usize, y: usize) -> bool {
+    ///
+    /// Fails if there is no undamaged ship of the player's at (x,y).
+    pub fn start_patrol(&mut self, x: usize, y: usize, events: &mut EventBus) -> Result<(), ActionError> {
+        self.base.board.check_bounds(x, y)?;
+
         // Find the ship at this position
         if let Some(ship_idx) = self.base.ships.iter().position(|ship| ship.positions.contains(&(x, y))) {
+            self.check_can_patrol(ship_idx)?;
             let ship = &self.base.ships[ship_idx];
-            
-            // Check if any part of the ship is hit
-            let has_hit = ship.positions.iter().any(|&(px, py)| {
-                self.base.board.cells[px][py] == Cells::Hit
-            });
-    
-            if has_hit {
-                println!("Cannot move a ship that has been hit!");
-                self.hand.push(ActionType::Patrol); // Return card to hand
-                return false;
-            }
     
             self.patrol_mode = true;
             self.patrol_ship = Some(ship_idx);
             self.patrol_frames = 30; // About 0.5 seconds at 60 FPS
-            
-            // Highlight ship
-            for &(px, py) in &self.base.ships[ship_idx].positions {
-                self.base.boardgrid.color_cell(px, py, YELLOW);
-            }
-            true
+            events.emit(GameEvent::PatrolStarted { player: self.base.id, ship_type: ship.ship_type, name: ship.name.clone() });
+            Ok(())
         } else {
-            self.hand.push(ActionType::Patrol); // Return card to hand
-            false
+            Err(ActionError::NotOwnShip { x, y })
+        }
+    }
+
+    /// Checks ship `ship_idx` exists and has not been hit, only undamaged ships can patrol.
+    pub fn check_can_patrol(&self, ship_idx: usize) -> Result<(), ActionError> {
+        let ship = self.base.ships.get(ship_idx).ok_or(ActionError::NoSuchShip(ship_idx))?;
+
+        // Check if any part of the ship is hit
+        let has_hit = ship.positions.iter().any(|&(px, py)| {
+            self.base.board.cells[px][py] == Cells::Hit
+        });
+
+        if has_hit {
+            return Err(ActionError::ShipDamaged);
+        }
+        Ok(())
+    }
+
+    /// Works out where ship `ship_idx` would be after moving one cell by (dir_x, dir_y), without moving it.
+    /// Fails if it would leave the board or run into another ship.
+    pub fn patrol_destination(&self, ship_idx: usize, dir_x: isize, dir_y: isize) -> Result<Vec<(usize, usize)>, ActionError> {
+        let ship = self.base.ships.get(ship_idx).ok_or(ActionError::NoSuchShip(ship_idx))?;
+
+        let mut new_positions = Vec::new();
+        for &(x, y) in &ship.positions {
+            let new_x = x as isize + dir_x;
+            let new_y = y as isize + dir_y;
+
+            // Check bounds
+            if !self.base.board.in_bounds_signed(new_x, new_y) {
+                return Err(ActionError::PatrolBlocked);
+            }
+            let (new_x, new_y) = (new_x as usize, new_y as usize);
+
+            // Check if new position is already taken by another ship, wrecks included so ships never overlap
+            let taken = self.base.ships.iter().enumerate()
+                .any(|(i, other)| i != ship_idx && other.positions.contains(&(new_x, new_y)));
+            if taken {
+                return Err(ActionError::PatrolBlocked);
+            }
+
+            new_positions.push((new_x, new_y));
         }
+        Ok(new_positions)
     }
 
     /// Attempts to move ship in patrol mode:
     /// - Checks new positions are within bounds
     /// - Prevents overlapping with other ships
     /// - Preserves Reinforced status during movement
-    /// Returns success status.
-    pub fn try_patrol_move(&mut self, dir_x: isize, dir_y: isize) -> bool {
+    ///
+    /// A blocked move leaves the ship selected so another direction can be tried.
+    pub fn try_patrol_move(&mut self, dir_x: isize, dir_y: isize, events: &mut EventBus) -> Result<(), ActionError> {
         if let Some(ship_idx) = self.patrol_ship {
+            // Calculate new positions
+            let new_positions = self.patrol_destination(ship_idx, dir_x, dir_y)?;
             let ship = &mut self.base.ships[ship_idx];
 
             // Track which positions were reinforced
@@ -319,33 +305,9 @@ impl TwistPlayer {
                 }
             }
 
-            // Calculate new positions
-            let mut new_positions = Vec::new();
+            // Clear old positions
             for &(x, y) in &ship.positions {
-                let new_x = (x as isize + dir_x) as usize;
-                let new_y = (y as isize + dir_y) as usize;
-
-                // Check bounds
-                if new_x >= GRID_SIZE || new_y >= GRID_SIZE {
-                    return false;
-                }
-
-                // Check if new position is already occupied (by another ship)
-                if self.base.board.cells[new_x][new_y] == Cells::Occupied || 
-                   self.base.board.cells[new_x][new_y] == Cells::Reinforced {
-                    // Check if this is part of our own ship
-                    if !ship.positions.contains(&(new_x, new_y)) {
-                        return false;
-                    }
-                }
-
-                new_positions.push((new_x, new_y));
-            }
-
-            // Clear old positions - change back to BLACK (default)
-            for &(x, y) in &ship.positions {
-                self.base.boardgrid.color_cell(x, y, BLACK);  // Set to black instead of changing cell type
-                self.base.board.cells[x][y] = Cells::Empty;   // Still mark as empty in the backend
+                self.base.board.cells[x][y] = Cells::Empty;
             }
 
             // Update ship positions
@@ -360,37 +322,26 @@ impl TwistPlayer {
                 ));
                 
                 if was_reinforced {
-                    self.base.board.change_cell(x, y, Cells::Reinforced, &mut self.base.boardgrid);
+                    self.base.board.change_cell(x, y, Cells::Reinforced);
                 } else {
-                    self.base.board.change_cell(x, y, Cells::Occupied, &mut self.base.boardgrid);
+                    self.base.board.change_cell(x, y, Cells::Occupied);
                 }
             }
 
+            events.emit(GameEvent::PatrolMoved { player: self.base.id, ship_type: ship.ship_type, name: ship.name.clone(), positions: ship.positions.clone() });
+
             // Clean up patrol state
             self.cancel_patrol(false);
-            true
+            Ok(())
         } else {
-            false
+            Err(ActionError::NotPatrolling)
         }
     }
 
     /// Clears patrol mode state:
-    /// - Removes ship highlights
-    /// - Resets tracking variables
+    /// - Resets tracking variables (which also removes the ship highlight)
     /// - `return_to_hand`: If true, returns Patrol card to hand
     pub fn cancel_patrol(&mut self, return_to_hand: bool) {
-        if let Some(ship_idx) = self.patrol_ship {
-            // Remove highlight and reset to proper colors
-            for &(x, y) in &self.base.ships[ship_idx].positions {
-                match self.base.board.cells[x][y] {
-                    Cells::Occupied => self.base.boardgrid.color_cell(x, y, GREEN),
-                    Cells::Reinforced => self.base.boardgrid.color_cell(x, y, DARKGREEN),
-                    Cells::Empty => self.base.boardgrid.color_cell(x, y, BLACK),
-                    _ => {}
-                }
-            }
-        }
-        
         // Return Patrol card to hand if specified
         if return_to_hand {
             self.hand.push(ActionType::Patrol);
@@ -405,89 +356,75 @@ impl TwistPlayer {
     /// - Decrements `patrol_frames` each frame (at ~60 FPS)
     /// - Automatically cancels patrol if timer expires
     /// - Returns patrol card to hand on timeout
-    pub fn update_patrol(&mut self) {
+    pub fn update_patrol(&mut self, events: &mut EventBus) {
         // Count down the patrol timer, disable patrol mode when it reaches 0
         if self.patrol_mode && self.patrol_frames > 0 {
             self.patrol_frames -= 1;
             
             if self.patrol_frames == 0 {
                 self.cancel_patrol(true); // Return to hand
-                println!("Patrol move timed out - card returned to hand");
+                events.emit(GameEvent::PatrolTimedOut { player: self.base.id });
             }
         }
     }
 
     /// Draws one card from the top of the deck.
-    /// - Returns `Some(ActionType)` if cards remain
-    /// - Returns `None` if deck is empty (game should handle reshuffling)
-    pub fn draw_card(&mut self) -> Option<ActionType> {
-        self.deck.deck_list.pop()
+    /// - Returns `Ok(ActionType)` if cards remain
+    /// - Returns `DeckExhausted` if deck is empty (game should handle reshuffling)
+    pub fn draw_card(&mut self, events: &mut EventBus) -> Result<ActionType, ActionError> {
+        let card = self.deck.deck_list.pop().ok_or(ActionError::DeckExhausted)?;
+        events.emit(GameEvent::CardDrawn { player: self.base.id, card });
+        Ok(card)
     }
 
     /// Ensures the player’s hand is filled to `HAND_SIZE`.
     /// If the deck runs out, it rebuilds and reshuffles automatically.
-    pub fn draw_hand(&mut self) {
+    pub fn draw_hand(&mut self, events: &mut EventBus, rng: &mut (impl Rng + ?Sized)) {
         while self.hand.len() < HAND_SIZE {
-            if let Some(card) = self.draw_card() {
+            if let Ok(card) = self.draw_card(events) {
                 self.hand.push(card);
             } else {
                 self.deck = Deck::new();
 
                 self.deck.build();
-                self.deck.shuffle();
-                self.draw_hand();
+                self.deck.shuffle(rng);
+                self.draw_hand(events, rng);
             }
         }
     }
 
+    /// Checks the player holds a card without using it.
+    /// Returns `CardNotInHand` if they don't, so an action can be refused before anything changes.
+    pub fn has_card(&self, action_type: ActionType) -> Result<(), ActionError> {
+        if self.hand.contains(&action_type) {
+            Ok(())
+        } else {
+            Err(ActionError::CardNotInHand(action_type))
+        }
+    }
+
     /// Uses the specified action card if available in hand.
     /// - Removes the card from hand if found.
     /// - Prevents playing unavailable cards.
-    /// Returns `true` if the card was used successfully, `false` otherwise.
-    pub fn use_card(&mut self, action_type: ActionType) -> bool {
+    ///
+    /// Returns `CardNotInHand` if the player doesn't hold the card.
+    pub fn use_card(&mut self, action_type: ActionType, events: &mut EventBus) -> Result<(), ActionError> {
         // If the card is in the player's hand, play it:
         if let Some(pos) = self.hand.iter().position(|&x| x == action_type) {
-            // Removes it from hand then returns true
+            // Removes it from hand
             self.hand.remove(pos);
-            true
+            events.emit(GameEvent::CardPlayed { player: self.base.id, card: action_type });
+            Ok(())
         } else {
-            // Returns false if the player doesn't have the card
-            false
+            Err(ActionError::CardNotInHand(action_type))
         }
     }
 }
 
-/// Renders action cards at bottom of screen:
-/// - Missile: Red
-/// - Torpedo: Blue
-/// - Patrol: Yellow
-/// - RadarScan: Purple
-/// - Reinforce: Green
-pub fn draw_hand_to_screen(hand: &[ActionType], x: f32, y: f32) {
-    for (i, card) in hand.iter().enumerate() {
-        let card_x = x + (i as f32 * 70.0);
-        let color = match card {
-            ActionType::Missile => RED,
-            ActionType::Torpedo => BLUE,
-            ActionType::Patrol => YELLOW,
-            ActionType::RadarScan => PURPLE,
-            ActionType::Reinforce => GREEN,
-        };
-        
-        draw_rectangle(card_x, y, 70.0, 100.0, color);
-        draw_text(
-            match card {
-                ActionType::Missile => "Missile",
-                ActionType::Torpedo => "Torpedo",
-                ActionType::Patrol => "Patrol",
-                ActionType::RadarScan => "Radar",
-                ActionType::Reinforce => "Reinforce",
-            },
-            card_x + 2.0,
-            y + 40.0,
-            20.0,
-            BLACK,
-        );
+/* ------ Trait Implementations ------ */
+/// An empty deck, the same as `Deck::new`
+impl Default for Deck {
+    fn default() -> Self {
+        Deck::new()
     }
 }
-
@@ -0,0 +1,336 @@
+//! Handles everything drawn on screen that isn't the main loop itself.
+//! The game rules in `base.rs` and `twist.rs` never touch a grid, so each frame the
+//! grids here are recoloured from the current player's boards before being drawn.
+//! The replay viewer shows a `PlayerView` for each player side by side, shrunk to fit.
+
+/* ------ Import Used Libraries ------ */
+// Uses the game rules
+use battleships::action::cell_name;
+use battleships::ai::{Advice, Difficulty};
+use battleships::base::*;
+use battleships::twist::*;
+// Graphics library
+use macroquad::prelude::*;
+// A module I recompiled and made small fixes to, but did not write. Used for grid graphics and logic.
+// Origional code avaliable here: https://github.com/TheDinner22/macroquad_grid
+extern crate macroquad_grid_dex;
+use macroquad_grid_dex::Grid;
+
+/*------ Constants ------ */
+const MAX_CELL_SIZE: f32 = 40.0;      // Largest a grid cell is drawn, in pixels
+const BOARD_AREA: f32 = 400.0;        // Longest side a board may take up on screen, in pixels
+const BOARD_X_OFFSET: f32 = 150.0;    // Left edge of the players own board
+const GUESS_X_OFFSET: f32 = 700.0;    // Left edge of the guess board
+const GRID_Y_OFFSET: f32 = 50.0;      // Top edge of both boards
+const LOG_LINES: usize = 3;           // How many recent messages are shown
+const ADVICE_ALPHA: f32 = 0.6;        // How strongly the advisor shades the likeliest cell, the rest are shaded in proportion
+const REPLAY_BOARD_AREA: f32 = 270.0; // Longest side of each of the four boards in the replay viewer, in pixels
+const REPLAY_BOARD_GAP: f32 = 35.0;   // Space between a player's two boards in the replay viewer
+const REPLAY_GRID_Y: f32 = 110.0;     // Top edge of the boards in the replay viewer
+const REPLAY_HALF_WIDTH: f32 = 640.0; // Width of each player's half of the screen in the replay viewer
+
+/*------ Enums and Structs ------ */
+/// The two visual grids for whichever player is currently on screen.
+pub struct PlayerView {
+    pub boardgrid: Grid, // Visual for the players own board.
+    pub guessgrid: Grid, // Visual of guesses
+    rows: usize,         // Board height in cells
+    cols: usize,         // Board width in cells
+    cell_size: f32,      // Size of a grid cell in pixels, shrinks so large boards still fit
+    board_x: f32,        // Left edge of the players own board
+    guess_x: f32,        // Left edge of the guess board
+    grid_y: f32,         // Top edge of both boards
+}
+
+/// Recent game feedback shown under the boards.
+pub struct MessageLog {
+    lines: Vec<String>, // Oldest first
+}
+
+/* ------ Struct Implementations ------ */
+impl PlayerView {
+    /// Creates both grids for a board `width` columns by `height` rows, with their offsets and colours.
+    pub fn new(width: usize, height: usize) -> Self {
+        PlayerView::placed(width, height, BOARD_AREA, BOARD_X_OFFSET, GUESS_X_OFFSET, GRID_Y_OFFSET)
+    }
+
+    /// Both grids of one player in the replay viewer, Player 1 on the left half of the screen and Player 2 on the right.
+    pub fn for_replay(width: usize, height: usize, id: PlayerId) -> Self {
+        let half = match id {
+            PlayerId::One => 0.0,
+            PlayerId::Two => REPLAY_HALF_WIDTH,
+        };
+        let board_x = half + REPLAY_BOARD_GAP;
+        let guess_x = board_x + REPLAY_BOARD_AREA + REPLAY_BOARD_GAP;
+        PlayerView::placed(width, height, REPLAY_BOARD_AREA, board_x, guess_x, REPLAY_GRID_Y)
+    }
+
+    /// Both grids with the longest side of each taking up to `area` pixels, the own board's left edge at
+    /// `board_x`, the guess board's at `guess_x` and both tops at `grid_y`.
+    fn placed(width: usize, height: usize, area: f32, board_x: f32, guess_x: f32, grid_y: f32) -> Self {
+        let cell_size = (area / width.max(height) as f32).min(MAX_CELL_SIZE);
+        let (px_width, px_height) = (cell_size * width as f32, cell_size * height as f32);
+        let mut v = PlayerView {
+            boardgrid: Grid::new(px_width, px_height, width, height, 1.0),
+            guessgrid: Grid::new(px_width, px_height, width, height, 1.0),
+            rows: height,
+            cols: width,
+            cell_size,
+            board_x,
+            guess_x,
+            grid_y,
+        };
+
+        // Change grid offset and cell colour for your board
+        v.boardgrid.set_x_offset(macroquad_grid_dex::Position::Pixels(board_x));
+        v.boardgrid.set_y_offset(macroquad_grid_dex::Position::Pixels(grid_y));
+        v.boardgrid.set_cell_bg_color(BLACK);
+        v.boardgrid.set_gap_color(LIME);
+        // Change grid offset and cell colour for the guess board
+        v.guessgrid.set_x_offset(macroquad_grid_dex::Position::Pixels(guess_x));
+        v.guessgrid.set_y_offset(macroquad_grid_dex::Position::Pixels(grid_y));
+        v.guessgrid.set_cell_bg_color(BLACK);
+        v.guessgrid.set_gap_color(LIME);
+
+        v
+    }
+
+    /// Left edge of the own board, in pixels
+    pub fn left(&self) -> f32 {
+        self.board_x
+    }
+
+    /// Bottom edge of both grids, in pixels
+    pub fn bottom(&self) -> f32 {
+        self.grid_y + self.cell_size * self.rows as f32
+    }
+
+    /// Writes a title above each grid
+    pub fn draw_titles(&self, board_title: &str, guess_title: &str) {
+        draw_text(board_title, self.board_x, self.grid_y - 10.0, 24.0, WHITE);
+        draw_text(guess_title, self.guess_x, self.grid_y - 10.0, 24.0, WHITE);
+    }
+
+    /// Recolours both grids from a classic player's boards.
+    /// Ships the player has sunk are shaded darker on the guess board.
+    pub fn sync(&mut self, player: &BasePlayer) {
+        for x in 0..self.rows {
+            for y in 0..self.cols {
+                self.boardgrid.color_cell(x, y, cell_color(player.board.cells[x][y]));
+                self.guessgrid.color_cell(x, y, cell_color(player.guess_board.cells[x][y]));
+            }
+        }
+
+        for ship in &player.sunk_ships {
+            for &(x, y) in &ship.positions {
+                self.guessgrid.color_cell(x, y, MAROON);
+            }
+        }
+    }
+
+    /// Recolours both grids from a twist player, adding radar scanned water
+    /// and the yellow highlight on a ship that is waiting for a patrol move.
+    pub fn sync_twist(&mut self, player: &TwistPlayer) {
+        self.sync(&player.base);
+
+        for &(x, y) in &player.scanned_cells {
+            if player.base.guess_board.cells[x][y] == Cells::Empty {
+                self.guessgrid.color_cell(x, y, DARKGRAY);
+            }
+        }
+
+        if let (true, Some(ship_idx)) = (player.patrol_mode, player.patrol_ship) {
+            for &(x, y) in &player.base.ships[ship_idx].positions {
+                self.boardgrid.color_cell(x, y, YELLOW);
+            }
+        }
+    }
+
+    /// Draws both grids
+    pub fn draw(&self) {
+        self.boardgrid.draw();
+        self.guessgrid.draw();
+    }
+
+    /// Draws the shot advisor over the guess grid: every cell shaded by how likely it is to hold a ship
+    /// compared to the likeliest one, the recommended target outlined, the best torpedo column boxed,
+    /// and a line underneath saying what they are.
+    pub fn draw_advice(&self, advice: &Advice) {
+        let most_likely = advice.chances.iter().flatten().fold(0.0, |most: f64, &chance| most.max(chance));
+        let cell_x = |y: usize| self.guess_x + y as f32 * self.cell_size;
+        let cell_y = |x: usize| self.grid_y + x as f32 * self.cell_size;
+
+        if most_likely > 0.0 {
+            for x in 0..self.rows {
+                for y in 0..self.cols {
+                    let strength = (advice.chances[x][y] / most_likely) as f32 * ADVICE_ALPHA;
+                    draw_rectangle(cell_x(y), cell_y(x), self.cell_size, self.cell_size, Color::new(1.0, 0.6, 0.0, strength));
+                }
+            }
+        }
+
+        let mut summary = Vec::new();
+        if let Some((x, y)) = advice.target {
+            draw_rectangle_lines(cell_x(y), cell_y(x), self.cell_size, self.cell_size, 4.0, YELLOW);
+            summary.push(format!("fire at {} ({:.0}%)", cell_name(x, y), advice.chances[x][y] * 100.0));
+        }
+        if let Some(col) = advice.torpedo {
+            draw_rectangle_lines(cell_x(col), cell_y(0), self.cell_size, self.cell_size * self.rows as f32, 3.0, SKYBLUE);
+            summary.push(format!("torpedo column {}", col + 1));
+        }
+        if !summary.is_empty() {
+            draw_text(&format!("Advisor: {}", summary.join(", ")), self.guess_x, self.bottom() + 25.0, 22.0, ORANGE);
+        }
+    }
+
+    /// Converts the mouse position to grid coordinates on a grid with the given left edge.
+    fn cell_under_mouse(&self, grid_x_offset: f32) -> Option<(usize, usize)> {
+        let (mouse_x, mouse_y) = mouse_position();
+        let grid_width_px = self.cell_size * self.cols as f32;
+        let grid_height_px = self.cell_size * self.rows as f32;
+
+        if mouse_x >= grid_x_offset && mouse_x < grid_x_offset + grid_width_px &&
+           mouse_y >= self.grid_y && mouse_y < self.grid_y + grid_height_px {
+            let x = ((mouse_y - self.grid_y) / self.cell_size) as usize;
+            let y = ((mouse_x - grid_x_offset) / self.cell_size) as usize;
+            return Some((x.min(self.rows - 1), y.min(self.cols - 1)));
+        }
+
+        None
+    }
+
+    /// Converts mouse position to grid coordinates on guess board.
+    /// Returns Some((x,y)) if the mouse is within the grid.
+    pub fn get_clicked_cell(&self) -> Option<(usize, usize)> {
+        self.cell_under_mouse(self.guess_x)
+    }
+
+    /// Gets vertical column for torpedo attacks:
+    /// - Maps mouse X-position to guess grid columns
+    /// - Returns `Some(usize)` if click within right-side grid
+    /// - Used exclusively for torpedo targeting
+    pub fn get_torpedo_target_column(&self) -> Option<usize> {
+        self.cell_under_mouse(self.guess_x).map(|(_, y)| y)
+    }
+
+    /// Converts mouse position to grid coordinates on the player's OWN board:
+    /// - Uses different grid offset than guess board
+    /// - Returns `Some((x, y))` if within placement grid bounds
+    /// - Used for ship reinforcement and patrol selection
+    pub fn get_clicked_cell_on_own_board(&self) -> Option<(usize, usize)> {
+        self.cell_under_mouse(self.board_x)
+    }
+}
+
+impl MessageLog {
+    /// Creates an empty log
+    pub fn new() -> Self {
+        MessageLog { lines: Vec::new() }
+    }
+
+    /// Adds a message, only the last few are kept.
+    pub fn push(&mut self, line: String) {
+        self.lines.push(line);
+        if self.lines.len() > LOG_LINES {
+            self.lines.remove(0);
+        }
+    }
+
+    /// Draws the messages from (x, y) downwards, newest last.
+    pub fn draw(&self, x: f32, y: f32) {
+        for (i, line) in self.lines.iter().enumerate() {
+            draw_text(line, x, y + i as f32 * 22.0, 22.0, WHITE);
+        }
+    }
+}
+
+/* ------ Functions ------ */
+/// Colour used to show each cell state
+pub fn cell_color(cell: Cells) -> Color {
+    match cell {
+        Cells::Empty => BLACK,
+        Cells::Occupied => GREEN,
+        Cells::Hit => RED,
+        Cells::Miss => GRAY,
+        Cells::Reinforced => DARKGREEN,
+    }
+}
+
+/// Start screen listing who can play as Player 2, the number keys pick an option.
+pub fn draw_opponent_menu(levels: &[Difficulty]) {
+    draw_text("BATTLESHIP", 150.0, 150.0, 60.0, WHITE);
+    draw_text("Who is Player 2?", 150.0, 220.0, 35.0, YELLOW);
+
+    draw_text("1 - Another person (pass the device between turns)", 170.0, 280.0, 28.0, WHITE);
+    for (i, level) in levels.iter().enumerate() {
+        let line = format!("{} - Computer ({})", i + 2, level.name());
+        draw_text(line.as_str(), 170.0, 320.0 + i as f32 * 40.0, 28.0, WHITE);
+    }
+
+    draw_text("Press Escape to quit", 150.0, 600.0, 25.0, GRAY);
+}
+
+pub fn draw_help_menu() {
+    // Semi-transparent background
+    draw_rectangle(100.0, 50.0, 1080.0, 620.0, Color::new(0.0, 0.0, 0.0, 0.85));
+
+    // Help title
+    draw_text("BATTLESHIP HELP", 150.0, 100.0, 40.0, WHITE);
+
+    // Common / shared Controls
+    draw_text("[Common Controls]", 150.0, 150.0, 30.0, YELLOW);
+    draw_text("- Left Click: Fire a missile", 170.0, 190.0, 25.0, WHITE);
+    draw_text("- Space: Confirm action/End turn", 170.0, 220.0, 25.0, WHITE);
+    draw_text("- A: Shot advisor (shades likely ship cells and outlines the best target)", 170.0, 250.0, 25.0, WHITE);
+    draw_text("- Q: Save and quit, carry on later with --resume", 170.0, 280.0, 25.0, WHITE);
+    draw_text("- D: Print your boards as text in the terminal", 170.0, 310.0, 25.0, WHITE);
+
+    // Classic Mode
+    draw_text("[Classic Mode]", 150.0, 355.0, 30.0, GREEN);
+    draw_text("- Just click to attack!", 170.0, 390.0, 25.0, WHITE);
+
+    // Twist Mode
+    draw_text("[Twist Mode]", 150.0, 435.0, 30.0, PURPLE);
+    draw_text("- T: Torpedo (vertical strike)", 170.0, 470.0, 25.0, WHITE);
+    draw_text("- R: Reinforce ship cell", 170.0, 500.0, 25.0, WHITE);
+    draw_text("- S: Radar Scan (reveals the selected cell and those adjacent)", 170.0, 530.0, 25.0, WHITE);
+    draw_text("- P: Patrol (move ships)", 170.0, 560.0, 25.0, WHITE);
+    draw_text("  - After pressing P: Use arrow keys to move", 190.0, 590.0, 25.0, WHITE);
+
+    // Close help menu
+    draw_text("Press H to close", 150.0, 630.0, 25.0, YELLOW);
+}
+
+/// Renders action cards at bottom of screen:
+/// - Missile: Red
+/// - Torpedo: Blue
+/// - Patrol: Yellow
+/// - RadarScan: Purple
+/// - Reinforce: Green
+pub fn draw_hand_to_screen(hand: &[ActionType], x: f32, y: f32) {
+    for (i, card) in hand.iter().enumerate() {
+        let card_x = x + (i as f32 * 70.0);
+        let color = match card {
+            ActionType::Missile => RED,
+            ActionType::Torpedo => BLUE,
+            ActionType::Patrol => YELLOW,
+            ActionType::RadarScan => PURPLE,
+            ActionType::Reinforce => GREEN,
+        };
+
+        draw_rectangle(card_x, y, 70.0, 100.0, color);
+        draw_text(
+            match card {
+                ActionType::Missile => "Missile",
+                ActionType::Torpedo => "Torpedo",
+                ActionType::Patrol => "Patrol",
+                ActionType::RadarScan => "Radar",
+                ActionType::Reinforce => "Reinforce",
+            },
+            card_x + 2.0,
+            y + 40.0,
+            20.0,
+            BLACK,
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Replay viewer.
+//! Steps through a match recorded with `--record` (see `replay.rs` in the library) with both fleets and both
+//! guess boards on screen at once, so a close game can be gone over or a disputed ruling checked move by move.
+//! Every position is rebuilt from the start of the replay through the rules, so stepping back is just
+//! playing one move fewer.
+
+/* ------ Import Used Libraries ------ */
+use crate::ui::*;
+// Uses the game rules
+use battleships::base::PlayerId;
+use battleships::error::ReplayError;
+use battleships::game::Phase;
+use battleships::replay::Replay;
+use battleships::rules::Mode;
+// Graphics library
+use macroquad::prelude::*;
+
+/*------ Constants ------ */
+const SPEEDS: [f32; 5] = [2.0, 1.0, 0.5, 0.25, 0.1]; // Seconds between moves at each autoplay speed, slowest first
+const START_SPEED: usize = 2;                         // Autoplay speed the viewer opens with
+const CAPTION_LINES: usize = 5;                       // Captions shown, the latest move's and the ones before it
+// Number keys in order, for typing the turn to jump to
+const DIGITS: [KeyCode; 10] = [
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+];
+
+/* ------ Functions ------ */
+/// Shows `replay` until Escape is pressed.
+/// Fails straight away if the replay can't be played through, as none of it could be trusted.
+pub async fn watch(replay: &Replay) -> Result<(), ReplayError> {
+    let captions = replay.captions()?;
+    let rules = &replay.start.rules;
+    let mut views = [
+        PlayerView::for_replay(rules.width, rules.height, PlayerId::One),
+        PlayerView::for_replay(rules.width, rules.height, PlayerId::Two),
+    ];
+
+    let mut shown = 0; // Moves played to reach the position on screen
+    let mut game = replay.game_after(shown)?;
+    let mut playing = false;
+    let mut speed = START_SPEED;
+    let mut timer = 0.0; // Time since autoplay last stepped
+    let mut typed = String::new(); // Turn number being typed
+
+    loop {
+        clear_background(BLACK);
+        let last = replay.moves.len();
+        // Round of the latest move played, or the first round before any
+        let turn = if shown > 0 { replay.moves[shown - 1].turn } else { game.turn };
+        let start_of = |turn: u32| replay.moves.iter().position(|step| step.turn >= turn).unwrap_or(last);
+
+        /* --- Input --- */
+        let mut target = shown;
+        if is_key_pressed(KeyCode::Right) {
+            target = shown + 1;
+        }
+        if is_key_pressed(KeyCode::Left) {
+            target = shown.saturating_sub(1);
+        }
+        if is_key_pressed(KeyCode::Up) {
+            target = start_of(turn + 1);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            // Back to the start of this turn, or the one before if already there
+            target = if start_of(turn) < shown { start_of(turn) } else { start_of(turn.saturating_sub(1)) };
+        }
+        if is_key_pressed(KeyCode::Home) {
+            target = 0;
+        }
+        if is_key_pressed(KeyCode::End) {
+            target = last;
+        }
+
+        /* --- Jump To Turn --- */
+        for (digit, key) in DIGITS.iter().enumerate() {
+            if is_key_pressed(*key) {
+                typed.push_str(&digit.to_string());
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            typed.pop();
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            if let Ok(turn) = typed.parse() {
+                target = start_of(turn);
+            }
+            typed.clear();
+        }
+
+        /* --- Autoplay --- */
+        if is_key_pressed(KeyCode::Space) {
+            playing = !playing;
+            timer = 0.0;
+        }
+        if is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd) {
+            speed = (speed + 1).min(SPEEDS.len() - 1);
+        }
+        if is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract) {
+            speed = speed.saturating_sub(1);
+        }
+        if playing {
+            timer += get_frame_time();
+            if timer >= SPEEDS[speed] {
+                timer = 0.0;
+                target = shown + 1;
+            }
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            return Ok(());
+        }
+
+        target = target.min(last);
+        if target != shown {
+            game = replay.game_after(target)?;
+            shown = target;
+        }
+        if shown == last {
+            playing = false;
+        }
+
+        /* --- Boards --- */
+        for (view, id) in views.iter_mut().zip([PlayerId::One, PlayerId::Two]) {
+            let player = game.player(id);
+            view.sync_twist(player);
+            view.draw();
+            view.draw_titles(&format!("{} fleet", id), &format!("{} shots", id));
+            if game.rules.mode == Mode::Twist {
+                draw_hand_to_screen(&player.hand, view.left(), view.bottom() + 20.0);
+            }
+        }
+
+        /* --- Position And Captions --- */
+        let status = match game.phase {
+            Phase::GameOver { winner, turns } => format!("Move {} of {}, {} won after {} turns", shown, last, winner, turns),
+            _ => format!("Move {} of {}, turn {}", shown, last, turn),
+        };
+        draw_text(&status, 35.0, 45.0, 30.0, WHITE);
+        let autoplay = if playing { format!("Autoplay: a move every {}s", SPEEDS[speed]) } else { "Paused".to_string() };
+        draw_text(&autoplay, 35.0, 75.0, 22.0, GRAY);
+        if !typed.is_empty() {
+            draw_text(&format!("Go to turn: {}", typed), screen_width() / 2.0, 75.0, 22.0, YELLOW);
+        }
+
+        // The latest move in white, the ones before it fading out above
+        let first = shown.saturating_sub(CAPTION_LINES);
+        let caption_y = screen_height() - 60.0 - CAPTION_LINES as f32 * 24.0;
+        for (i, caption) in captions[first..shown].iter().enumerate() {
+            let color = if first + i + 1 == shown { WHITE } else { GRAY };
+            draw_text(&format!("{}. {}", first + i + 1, caption), 35.0, caption_y + i as f32 * 24.0, 24.0, color);
+        }
+
+        draw_text(
+            "Left/Right: step  Up/Down: turn  Home/End: start/end  Space: autoplay  +/-: speed  Number then Enter: go to turn  Escape: quit",
+            35.0, screen_height() - 20.0, 20.0, GRAY,
+        );
+
+        next_frame().await;
+    }
+}